@@ -0,0 +1,47 @@
+
+extern crate pcd8544;
+
+use pcd8544::{PCD8544, Orientation};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// The size of the seven-segment digits.
+const DIGIT_WIDTH  : usize = 14;
+const DIGIT_HEIGHT : usize = 28;
+const THICKNESS    : usize = 3;
+
+fn main() {
+    // Pin numbers for an Adafruit-style wiring on a Raspberry Pi.
+    let mut lcd = PCD8544::new(23, 24, "/dev/spidev0.0", Orientation::Landscape(false))
+        .expect("Failed to open the display");
+
+    // HH:MM is four digits, a colon and four gaps, as wide as the colon.
+    let clock_width = 4 * DIGIT_WIDTH + 5 * THICKNESS;
+    let x = (lcd.width() - clock_width) / 2;
+    let y = (lcd.height() - DIGIT_HEIGHT) / 2;
+
+    lcd.clear();
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).expect("The clock is before 1970");
+        // The time of day in UTC.
+        let seconds = now.as_secs() % 86_400;
+        let hours = (seconds / 3600) as i32;
+        let minutes = (seconds / 60 % 60) as i32;
+
+        let xp = lcd.draw_number_padded(x, y, hours, 2, DIGIT_WIDTH, DIGIT_HEIGHT, THICKNESS);
+        let xp = lcd.draw_colon(xp + THICKNESS, y, DIGIT_HEIGHT, THICKNESS, seconds.is_multiple_of(2));
+        lcd.draw_number_padded(xp + THICKNESS, y, minutes, 2, DIGIT_WIDTH, DIGIT_HEIGHT, THICKNESS);
+
+        // A bar along the bottom edge shows the seconds.
+        let bar = (seconds % 60) as usize * lcd.width() / 59;
+        lcd.hline(0, lcd.height() - 2, bar, true);
+        lcd.hline(bar, lcd.height() - 2, lcd.width() - bar, false);
+
+        // The digits are redrawn every second: only send the bytes that
+        // changed, usually the colon and the end of the bar.
+        lcd.update_diff().expect("Failed to update the display");
+
+        // Wake up at the start of the next second.
+        sleep(Duration::from_millis(1000 - now.subsec_millis() as u64));
+    }
+}
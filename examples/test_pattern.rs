@@ -0,0 +1,27 @@
+
+extern crate pcd8544;
+
+use pcd8544::{PCD8544, Orientation, TestPattern};
+use std::thread::sleep;
+use std::time::Duration;
+
+fn main() {
+    // Pin numbers for an Adafruit-style wiring on a Raspberry Pi.
+    let mut lcd = PCD8544::new(23, 24, "/dev/spidev0.0", Orientation::Landscape(false))
+        .expect("Failed to open the display");
+
+    let patterns = [
+        TestPattern::Border,
+        TestPattern::Checkerboard,
+        TestPattern::VerticalStripes,
+        TestPattern::HorizontalStripes,
+        TestPattern::Gradient
+    ];
+
+    for &p in patterns.iter() {
+        println!("{:?}", p);
+        lcd.fill_test_pattern(p);
+        lcd.update().expect("Failed to update the display");
+        sleep(Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,24 @@
+
+extern crate pcd8544;
+
+use pcd8544::{PCD8544, Orientation};
+
+// A 16x16 logo, as exported by GIMP in XBM format.
+const LOGO_WIDTH  : usize = 16;
+const LOGO_HEIGHT : usize = 16;
+const LOGO_BITS : [u8 ; 32] = [
+    0xe0, 0x07, 0x18, 0x18, 0x04, 0x20, 0x02, 0x40,
+    0x32, 0x4c, 0x31, 0x8c, 0x01, 0x80, 0x01, 0x80,
+    0x01, 0x80, 0x09, 0x90, 0x11, 0x88, 0xe2, 0x47,
+    0x02, 0x40, 0x04, 0x20, 0x18, 0x18, 0xe0, 0x07
+];
+
+fn main() {
+    // Pin numbers for an Adafruit-style wiring on a Raspberry Pi.
+    let mut lcd = PCD8544::new(23, 24, "/dev/spidev0.0", Orientation::Landscape(false))
+        .expect("Failed to open the display");
+
+    lcd.clear();
+    lcd.draw_xbm(34, 16, LOGO_WIDTH, LOGO_HEIGHT, &LOGO_BITS);
+    lcd.update().expect("Failed to update the display");
+}
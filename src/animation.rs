@@ -0,0 +1,80 @@
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use std::thread::sleep;
+use std::time::Duration;
+use crate::{PCD8544, Result, BUFFER_LEN};
+
+/// A sequence of frames, in the layout of the display memory.
+pub struct Animation {
+    pub frames : Vec<[u8 ; BUFFER_LEN]>,
+    /// The delay between two frames.
+    pub frame_delay : Duration
+}
+
+/// Play an animation one frame at a time, for event loops.
+pub struct AnimationPlayer<'a> {
+    anim : &'a Animation,
+    next : usize,
+    looping : bool
+}
+
+impl<'a> AnimationPlayer<'a> {
+    pub fn new(anim : &'a Animation) -> Self {
+        Self { anim, next : 0, looping : false }
+    }
+
+    /// Restart from the first frame after the last one.
+    pub fn looping(mut self, looping : bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Whether all the frames have been shown.
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.next >= self.anim.frames.len()
+    }
+
+    /// Show the next frame on the display.
+    ///
+    /// Returns false, without updating the display, when the animation is
+    /// finished. The caller is responsible for waiting `frame_delay` between
+    /// two calls.
+    pub fn tick<SPI, DC, RST, BL, SpiE, PinE>(&mut self, lcd : &mut PCD8544<SPI, DC, RST, BL>) -> Result<bool, SpiE, PinE>
+        where SPI : spi::Write<u8, Error = SpiE>,
+              DC  : OutputPin<Error = PinE>,
+              RST : OutputPin<Error = PinE> {
+        if self.looping && self.next >= self.anim.frames.len() {
+            self.next = 0;
+        }
+        match self.anim.frames.get(self.next) {
+            Some(frame) => {
+                lcd.set_buffer(frame)?;
+                lcd.update()?;
+                self.next += 1;
+                Ok(true)
+            }
+            None => Ok(false)
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Show all the frames of an animation, waiting `frame_delay` between
+    /// two frames.
+    ///
+    /// This returns as soon as the last frame is shown.
+    pub fn play_animation(&mut self, anim : &Animation) -> Result<(), SpiE, PinE> {
+        let mut player = AnimationPlayer::new(anim);
+        while player.tick(self)? {
+            if !player.is_finished() {
+                sleep(anim.frame_delay);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+
+use core::cmp::max;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use crate::{PCD8544, NoBacklight, Result, TAB_STOP};
+
+/// Several displays forming a single canvas, such as two panels side by side
+/// for a 168x48 display.
+///
+/// The panels are arranged in rows of `columns` panels, from left to right
+/// and from top to bottom. Each panel keeps its own buffer, orientation and
+/// bus: panels sharing an SPI bus with separate chip selects need a bus type
+/// that selects the right one, such as the proxies of a bus sharing crate.
+pub struct PCD8544Array<SPI, DC, RST, BL = NoBacklight> {
+    panels : Vec<PCD8544<SPI, DC, RST, BL>>,
+    columns : usize
+}
+
+impl<SPI, DC, RST, BL> PCD8544Array<SPI, DC, RST, BL> {
+    /// Arrange the given panels in rows of `columns` panels.
+    ///
+    /// All panels take the dimensions of the first one in its current
+    /// orientation: they should have the same orientation. Text uses the
+    /// font, code page and spacing of the first panel.
+    pub fn new(panels : Vec<PCD8544<SPI, DC, RST, BL>>, columns : usize) -> Self {
+        Self {
+            panels,
+            columns : max(columns, 1)
+        }
+    }
+
+    pub fn panels(&self) -> &[PCD8544<SPI, DC, RST, BL>] {
+        &self.panels
+    }
+
+    pub fn panels_mut(&mut self) -> &mut [PCD8544<SPI, DC, RST, BL>] {
+        &mut self.panels
+    }
+
+    /// Give back the panels, in the order given to `new`.
+    pub fn into_panels(self) -> Vec<PCD8544<SPI, DC, RST, BL>> {
+        self.panels
+    }
+
+    // The dimensions of a single panel.
+    fn panel_dimensions(&self) -> (usize, usize) {
+        self.panels.first().map_or((0, 0), |p| p.dimensions())
+    }
+
+    // The number of panels in each column and each row.
+    fn grid(&self) -> (usize, usize) {
+        let cols = self.columns.min(self.panels.len());
+        (cols, self.panels.len().div_ceil(self.columns))
+    }
+
+    /// The width of the canvas in pixels.
+    pub fn width(&self) -> usize {
+        self.panel_dimensions().0 * self.grid().0
+    }
+
+    /// The height of the canvas in pixels.
+    pub fn height(&self) -> usize {
+        self.panel_dimensions().1 * self.grid().1
+    }
+
+    // Map canvas coordinates to a panel index and coordinates in that panel.
+    fn locate(&self, x : usize, y : usize) -> Option<(usize, usize, usize)> {
+        let (pw, ph) = self.panel_dimensions();
+        if pw == 0 || ph == 0 || x >= self.width() {
+            return None
+        }
+        let k = (y / ph) * self.columns + x / pw;
+        if k < self.panels.len() {
+            Some((k, x % pw, y % ph))
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for p in self.panels.iter_mut() {
+            p.clear();
+        }
+    }
+
+    pub fn set_pixel(&mut self, x : usize, y : usize, value : bool) {
+        if let Some((k, px, py)) = self.locate(x, y) {
+            self.panels[k].set_pixel(px, py, value);
+        }
+    }
+
+    pub fn get_pixel(&self, x : usize, y : usize) -> bool {
+        match self.locate(x, y) {
+            Some((k, px, py)) => self.panels[k].get_pixel(px, py),
+            None              => false
+        }
+    }
+
+    /// Draw a line across the canvas.
+    ///
+    /// Each panel draws the whole line, clipped to its area, so that the
+    /// pixels on both sides of a seam are those of a single line.
+    pub fn draw_line(&mut self, x0 : usize, y0 : usize, x1 : usize, y1 : usize, value : bool) {
+        let (pw, ph) = self.panel_dimensions();
+        let columns = self.columns;
+        for (k, p) in self.panels.iter_mut().enumerate() {
+            let ox = ((k % columns) * pw) as isize;
+            let oy = ((k / columns) * ph) as isize;
+            p.line_signed(x0 as isize - ox, y0 as isize - oy, x1 as isize - ox, y1 as isize - oy, value);
+        }
+    }
+
+    /// Print a string from the given character coordinates, as `print` does
+    /// on a single panel from left to right.
+    ///
+    /// Characters are placed on the canvas, so a character that crosses the
+    /// seam between two panels is drawn partly on each of them. The cells
+    /// have the size of the font of the first panel, and each panel draws
+    /// with its own font and code page, which should be the same for all.
+    pub fn print(&mut self, x : usize, y : usize, s : &str) {
+        let Some(first) = self.panels.first() else {
+            return
+        };
+        let cell_width  = first.font.width() + first.char_spacing;
+        let cell_height = first.font.height();
+        let tab_width = TAB_STOP * cell_width;
+        let (width, height) = (self.width(), self.height());
+        let mut xp = x * cell_width;
+        let mut yc = y;
+        for c in s.chars() {
+            match c {
+                '\n' => {
+                    xp = x * cell_width;
+                    yc += 1;
+                }
+                '\r' => xp = x * cell_width,
+                '\t' => xp = (xp / tab_width + 1) * tab_width,
+                _    => {
+                    self.draw_char_at(xp, yc * cell_height, c);
+                    xp += self.panels[0].glyph_advance(c);
+                }
+            }
+            if xp >= width {
+                xp = 0;
+                yc += 1;
+            }
+            if yc * cell_height >= height {
+                break;
+            }
+        }
+    }
+
+    // Draw a character with its top left corner at the given canvas
+    // coordinates: each panel draws the part that falls on it.
+    fn draw_char_at(&mut self, xp : usize, yp : usize, c : char) {
+        let (pw, ph) = self.panel_dimensions();
+        let columns = self.columns;
+        for (k, p) in self.panels.iter_mut().enumerate() {
+            let ox = ((k % columns) * pw) as isize;
+            let oy = ((k / columns) * ph) as isize;
+            p.draw_char_clipped(xp as isize - ox, yp as isize - oy, c, 1, pw);
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544Array<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Update each panel with its own buffer, in order.
+    ///
+    /// Only the modified part of each buffer is sent, so the panels that
+    /// were not drawn on are skipped.
+    pub fn update(&mut self) -> Result<(), SpiE, PinE> {
+        for p in self.panels.iter_mut() {
+            p.update()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use core::cmp::min;
+use core::future::Future;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use std::panic;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::task::spawn_blocking;
+use tokio::time::sleep;
+use crate::{PCD8544, Error, Result, address_commands, BUFFER_LEN, RESET_DELAY_MS, RETRY_DELAY_MS};
+
+/// An SPI bus whose writes can be awaited, as used by `update_async`.
+pub trait AsyncSpiWrite {
+    type Error;
+
+    /// Send the given bytes, completing when all of them were sent.
+    fn write_async(&mut self, words : &[u8]) -> impl Future<Output = core::result::Result<(), Self::Error>>;
+}
+
+/// A blocking SPI bus whose asynchronous writes run in the thread pool of
+/// tokio for blocking code, with `spawn_blocking`.
+///
+/// The bytes are copied for each asynchronous write. A write that was
+/// started keeps running in the background if its future is dropped.
+pub struct BlockingSpi<SPI>(Arc<Mutex<SPI>>);
+
+impl<SPI> BlockingSpi<SPI> {
+    pub fn new(spi : SPI) -> Self {
+        BlockingSpi(Arc::new(Mutex::new(spi)))
+    }
+}
+
+impl<SPI : spi::Write<u8>> spi::Write<u8> for BlockingSpi<SPI> {
+    type Error = SPI::Error;
+
+    fn write(&mut self, words : &[u8]) -> core::result::Result<(), SPI::Error> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).write(words)
+    }
+}
+
+impl<SPI> AsyncSpiWrite for BlockingSpi<SPI>
+    where SPI        : spi::Write<u8> + Send + 'static,
+          SPI::Error : Send + 'static {
+    type Error = SPI::Error;
+
+    async fn write_async(&mut self, words : &[u8]) -> core::result::Result<(), SPI::Error> {
+        let bus = self.0.clone();
+        let words = words.to_vec();
+        let res = spawn_blocking(move || {
+            bus.lock().unwrap_or_else(PoisonError::into_inner).write(&words)
+        }).await;
+        // A blocking task is only cancelled when the runtime shuts down,
+        // and into_panic panics in that case too.
+        res.unwrap_or_else(|e| panic::resume_unwind(e.into_panic()))
+    }
+}
+
+// Write to the bus asynchronously, making up to `attempts` attempts and
+// returning the last error.
+async fn write_async_with_retries<SPI : AsyncSpiWrite>(spi : &mut SPI, words : &[u8], attempts : u32) -> core::result::Result<(), SPI::Error> {
+    let mut res = spi.write_async(words).await;
+    for _ in 1..attempts {
+        if res.is_ok() {
+            break;
+        }
+        sleep(Duration::from_millis(RETRY_DELAY_MS as u64)).await;
+        res = spi.write_async(words).await;
+    }
+    res
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Same as `reset`, but waits with `tokio::time::sleep` instead of
+    /// blocking the thread.
+    pub async fn reset_async(&mut self) -> Result<(), SpiE, PinE> {
+        self.rst.set_low().map_err(Error::PinError)?;
+        sleep(Duration::from_millis(RESET_DELAY_MS as u64)).await;
+        self.rst.set_high().map_err(Error::PinError)?;
+        self.forget_display_memory();
+        self.dirty = Some((0, BUFFER_LEN - 1));
+        Ok(())
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE> + AsyncSpiWrite<Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Same as `update`, with the asynchronous writes of the bus.
+    ///
+    /// A blocking bus can be wrapped in a `BlockingSpi`, so that the
+    /// transfers run outside of the executor threads.
+    pub async fn update_async(&mut self) -> Result<(), SpiE, PinE> {
+        #[cfg(feature = "double-buffer")]
+        if self.double_buffered {
+            if self.needs_full_update() {
+                return self.send_async(0, BUFFER_LEN).await
+            }
+            let mut index = 0;
+            while let Some((start, end)) = self.next_changed_range(index) {
+                self.send_async(start, end).await?;
+                index = end;
+            }
+            return Ok(())
+        }
+        if let Some((start, end)) = self.dirty {
+            self.send_async(start, end + 1).await?;
+            self.dirty = None;
+        }
+        Ok(())
+    }
+
+    // Same as send_address followed by send_buffer, with asynchronous
+    // writes.
+    async fn send_async(&mut self, start : usize, end : usize) -> Result<(), SpiE, PinE> {
+        let attempts = self.write_attempts;
+        self.dc.set_low().map_err(Error::PinError)?;
+        for c in address_commands(start).iter() {
+            write_async_with_retries(&mut self.spi, &[*c], attempts).await.map_err(Error::SpiDevError)?;
+            self.state.record(*c);
+        }
+
+        self.dc.set_high().map_err(Error::PinError)?;
+        let mut pos = start;
+        while pos < end {
+            let next = min(pos + self.max_transfer, end);
+            let (frame, spi) = self.frame_and_bus();
+            write_async_with_retries(spi, &frame[pos .. next], attempts).await.map_err(Error::SpiDevError)?;
+            pos = next;
+        }
+        #[cfg(feature = "diff-update")]
+        self.record_sent(start, end);
+        Ok(())
+    }
+}
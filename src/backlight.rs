@@ -0,0 +1,78 @@
+
+use core::convert::Infallible;
+use embedded_hal::PwmPin;
+use embedded_hal::digital::v2::OutputPin;
+use crate::PCD8544;
+
+/// A backlight with adjustable brightness.
+pub trait Backlight {
+    type Error;
+
+    /// Set the brightness, from 0 (off) to 255 (full brightness).
+    fn set_level(&mut self, level : u8) -> Result<(), Self::Error>;
+}
+
+/// The backlight of a display without backlight control.
+pub struct NoBacklight;
+
+impl Backlight for NoBacklight {
+    type Error = Infallible;
+
+    fn set_level(&mut self, _ : u8) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// A backlight driven by a GPIO pin, either on or off.
+///
+/// Any non-zero level turns the backlight on.
+pub struct BacklightPin<P>(pub P);
+
+impl<P : OutputPin> Backlight for BacklightPin<P> {
+    type Error = P::Error;
+
+    fn set_level(&mut self, level : u8) -> Result<(), P::Error> {
+        if level > 0 {
+            self.0.set_high()
+        }
+        else {
+            self.0.set_low()
+        }
+    }
+}
+
+/// A backlight driven by a PWM output.
+pub struct BacklightPwm<P>(pub P);
+
+impl<P : PwmPin<Duty = u16>> Backlight for BacklightPwm<P> {
+    type Error = Infallible;
+
+    fn set_level(&mut self, level : u8) -> Result<(), Infallible> {
+        let duty = self.0.get_max_duty() as u32 * level as u32 / 255;
+        self.0.set_duty(duty as u16);
+        self.0.enable();
+        Ok(())
+    }
+}
+
+/// An optional backlight: setting the level of a missing backlight does nothing.
+impl<B : Backlight> Backlight for Option<B> {
+    type Error = B::Error;
+
+    fn set_level(&mut self, level : u8) -> Result<(), B::Error> {
+        match *self {
+            Some(ref mut b) => b.set_level(level),
+            None            => Ok(())
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL : Backlight> PCD8544<SPI, DC, RST, BL> {
+    pub fn set_backlight(&mut self, on : bool) -> Result<(), BL::Error> {
+        self.backlight.set_level(if on { 255 } else { 0 })
+    }
+
+    pub fn set_backlight_level(&mut self, duty : u8) -> Result<(), BL::Error> {
+        self.backlight.set_level(duty)
+    }
+}
@@ -0,0 +1,38 @@
+
+use crate::PCD8544;
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Draw a 1 bit-per-pixel bitmap.
+    ///
+    /// `data` contains `h` rows of `ceil(w / 8)` bytes, where the most
+    /// significant bit is the leftmost pixel. Set bits are drawn as
+    /// pixels on; clear bits are drawn as pixels off unless `transparent`
+    /// is true.
+    pub fn draw_bitmap(&mut self, x : usize, y : usize, w : usize, h : usize, data : &[u8], transparent : bool) {
+        let row_bytes = w.div_ceil(8);
+        for r in 0..h {
+            for k in 0..w {
+                let b = data.get(r * row_bytes + k / 8).cloned().unwrap_or(0x00);
+                let value = (b & (0x80 >> (k % 8))) != 0x00;
+                if value || !transparent {
+                    self.set_pixel(x + k, y + r, value);
+                }
+            }
+        }
+    }
+
+    /// Draw an image in X BitMap (XBM) format.
+    ///
+    /// `bits` contains `height` rows of `ceil(width / 8)` bytes. Unlike
+    /// `draw_bitmap` and the fonts, the *least* significant bit of each byte
+    /// is the leftmost pixel, as in the `_bits` array of an XBM file.
+    pub fn draw_xbm(&mut self, x : usize, y : usize, width : usize, height : usize, bits : &[u8]) {
+        let row_bytes = width.div_ceil(8);
+        for r in 0..height {
+            for k in 0..width {
+                let b = bits.get(r * row_bytes + k / 8).cloned().unwrap_or(0x00);
+                self.set_pixel(x + k, y + r, (b & (0x01 << (k % 8))) != 0x00);
+            }
+        }
+    }
+}
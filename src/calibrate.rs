@@ -0,0 +1,77 @@
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use std::thread::sleep;
+use std::time::Duration;
+use crate::{PCD8544, Result, TestPattern, BUFFER_LEN};
+
+// The contrast values tried by calibrate_contrast.
+const SWEEP_MIN  : u8 = 20;
+const SWEEP_MAX  : u8 = 100;
+const SWEEP_STEP : usize = 4;
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Show a test pattern and increase the contrast step by step, from 20
+    /// to 100, until `accept` returns true.
+    ///
+    /// `accept` is called with each contrast value after it has been
+    /// displayed for `step_delay`. Returns the accepted value, which remains
+    /// active, or `None` after restoring the initial contrast. The content of
+    /// the buffer and of the display is restored in both cases, and when a
+    /// write fails during the sweep, before returning its error.
+    ///
+    /// While drawing to the back buffer, the pattern replaces the presented
+    /// frame during the sweep, and the back buffer is left untouched.
+    pub fn calibrate_contrast<F>(&mut self, step_delay : Duration, accept : F) -> Result<Option<u8>, SpiE, PinE>
+        where F : FnMut(u8) -> bool {
+        let saved = self.buffer;
+        #[cfg(feature = "double-buffer")]
+        let (saved_front, saved_dirty) = (self.front, self.dirty);
+        let initial = self.contrast;
+
+        let res = self.contrast_sweep(step_delay, accept);
+
+        self.buffer = saved;
+        self.dirty = Some((0, BUFFER_LEN - 1));
+        #[cfg(feature = "double-buffer")]
+        if self.double_buffered {
+            self.front = saved_front;
+            self.dirty = saved_dirty;
+        }
+        let contrast = match res {
+            Ok(Some(_)) => Ok(()),
+            _           => self.set_contrast(initial)
+        };
+        let shown = self.update_full();
+
+        // The error of the sweep comes first.
+        let c = res?;
+        contrast?;
+        shown?;
+        Ok(c)
+    }
+
+    // Show the test pattern in the active buffer and try the contrast values.
+    fn contrast_sweep<F>(&mut self, step_delay : Duration, mut accept : F) -> Result<Option<u8>, SpiE, PinE>
+        where F : FnMut(u8) -> bool {
+        self.fill_test_pattern(TestPattern::Gradient);
+        #[cfg(feature = "double-buffer")]
+        if self.double_buffered {
+            self.front = self.buffer;
+        }
+        self.update_full()?;
+
+        for c in (SWEEP_MIN ..= SWEEP_MAX).step_by(SWEEP_STEP) {
+            self.set_contrast(c)?;
+            sleep(step_delay);
+            if accept(c) {
+                return Ok(Some(c))
+            }
+        }
+        Ok(None)
+    }
+}
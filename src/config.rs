@@ -0,0 +1,52 @@
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use crate::{PCD8544, Orientation, Result};
+
+/// The settings of a display, to save and restore them.
+///
+/// With the `serde` feature, this type implements `Serialize` and
+/// `Deserialize`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Config {
+    pub contrast : u8,
+    pub bias : u8,
+    pub orientation : Orientation,
+    pub char_spacing : usize,
+    pub inverse : bool
+}
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// The current settings of the display.
+    pub fn current_config(&self) -> Config {
+        Config {
+            contrast : self.contrast,
+            bias : self.bias,
+            orientation : self.orient,
+            char_spacing : self.char_spacing,
+            inverse : self.inverse
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Send the contrast and bias of a configuration to the controller, and
+    /// use its other settings for the next drawing operations.
+    ///
+    /// The content of the buffer is not modified: redraw it after changing
+    /// the orientation or `inverse`. A bias out of range is reported before
+    /// any setting is changed.
+    pub fn apply_config(&mut self, config : &Config) -> Result<(), SpiE, PinE> {
+        self.set_bias(config.bias)?;
+        self.set_contrast(config.contrast)?;
+        self.orient = config.orientation;
+        self.char_spacing = config.char_spacing;
+        self.inverse = config.inverse;
+        Ok(())
+    }
+}
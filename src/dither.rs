@@ -0,0 +1,199 @@
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use image::GrayImage;
+#[cfg(feature = "image-file")]
+use std::fs;
+#[cfg(feature = "image-file")]
+use std::path::Path;
+#[cfg(feature = "image-file")]
+use crate::Error;
+use crate::{PCD8544, Result, BUFFER_LEN};
+use crate::pattern::BAYER4;
+
+/// The method used to convert a grayscale image to black and white pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dither {
+    /// Dark pixels are the ones below the given level.
+    Threshold(u8),
+    /// Spread the quantization error of each pixel to its neighbors.
+    FloydSteinberg,
+    /// Compare the pixels with a 4x4 Bayer matrix.
+    Ordered
+}
+
+// The maximum number of bit planes of a temporal grayscale image.
+const MAX_GRAY_BITS : u32 = 4;
+
+// An image shown with binary-coded modulation: plane k, with bit k of the
+// darkness of each pixel, is shown for 2^k subframes.
+pub(crate) struct GrayPlanes {
+    planes : Vec<[u8 ; BUFFER_LEN]>,
+    // The pixels of the buffer that belong to the image.
+    mask : [u8 ; BUFFER_LEN],
+    subframe : usize
+}
+
+impl GrayPlanes {
+    // The plane shown in the current subframe.
+    fn current(&self) -> &[u8 ; BUFFER_LEN] {
+        let k = (usize::BITS - (self.subframe + 1).leading_zeros() - 1) as usize;
+        &self.planes[k]
+    }
+
+    // Copy the pixels of the current subframe to a frame.
+    fn apply(&self, frame : &mut [u8 ; BUFFER_LEN]) {
+        let plane = self.current();
+        for (i, b) in frame.iter_mut().enumerate() {
+            *b = (*b & !self.mask[i]) | (plane[i] & self.mask[i]);
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Draw a grayscale image, with pixels on where the image is dark.
+    pub fn draw_gray(&mut self, x : usize, y : usize, img : &GrayImage, dither : Dither) {
+        let (w, h) = (img.width() as usize, img.height() as usize);
+        match dither {
+            Dither::Threshold(level) => {
+                for (px, py, p) in img.enumerate_pixels() {
+                    self.set_pixel(x + px as usize, y + py as usize, p[0] < level);
+                }
+            }
+            Dither::Ordered => {
+                for (px, py, p) in img.enumerate_pixels() {
+                    // Map the matrix to thresholds 8, 24 ... 248.
+                    let level = BAYER4[py as usize % 4][px as usize % 4] * 16 + 8;
+                    self.set_pixel(x + px as usize, y + py as usize, p[0] < level);
+                }
+            }
+            Dither::FloydSteinberg => {
+                // Accumulated errors for the current and the next row,
+                // with one extra cell on each side.
+                let mut cur  = vec![0i16 ; w + 2];
+                let mut next = vec![0i16 ; w + 2];
+                for py in 0..h {
+                    for px in 0..w {
+                        let v = img.get_pixel(px as u32, py as u32)[0] as i16 + cur[px + 1] / 16;
+                        let dark = v < 128;
+                        let err = if dark { v } else { v - 255 };
+                        cur[px + 2]  += err * 7;
+                        next[px]     += err * 3;
+                        next[px + 1] += err * 5;
+                        next[px + 2] += err;
+                        self.set_pixel(x + px, y + py, dark);
+                    }
+                    cur = next;
+                    next = vec![0i16 ; w + 2];
+                }
+            }
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Draw a grayscale image with `levels` gray levels, from 2 to 16,
+    /// emulated by turning the pixels on for a part of the subframes shown
+    /// by `tick_gray`.
+    ///
+    /// `levels` is rounded up to a power of two 2^n, and a cycle lasts
+    /// 2^n - 1 subframes. The image is kept in n bit planes of 504 bytes.
+    /// Drawing over the image has no lasting effect until `stop_gray`.
+    pub fn draw_gray_temporal(&mut self, x : usize, y : usize, img : &GrayImage, levels : u8) {
+        let bits = (levels.clamp(2, 1 << MAX_GRAY_BITS) as u32 - 1).ilog2() + 1;
+        let fill = if self.inverse { 0xFF } else { 0x00 };
+        let keep = match self.gray.take() {
+            Some(g) if g.planes.len() == bits as usize => Some(g),
+            _ => None
+        };
+        let mut gray = keep.unwrap_or_else(|| Box::new(GrayPlanes {
+            planes : vec![[fill ; BUFFER_LEN] ; bits as usize],
+            mask : [0x00 ; BUFFER_LEN],
+            subframe : 0
+        }));
+
+        let max_level = (1u32 << bits) - 1;
+        for (px, py, p) in img.enumerate_pixels() {
+            if let Some((index, bv)) = self.pixel_location(x + px as usize, y + py as usize) {
+                let level = ((255 - p[0] as u32) * max_level + 127) / 255;
+                gray.mask[index] |= bv;
+                for (k, plane) in gray.planes.iter_mut().enumerate() {
+                    if (level >> k & 1 != 0) != self.inverse {
+                        plane[index] |= bv;
+                    }
+                    else {
+                        plane[index] &= !bv;
+                    }
+                }
+            }
+        }
+        self.gray = Some(gray);
+        self.show_gray_subframe();
+    }
+
+    /// Stop the temporal grayscale emulation, leaving the current subframe
+    /// in the buffer.
+    pub fn stop_gray(&mut self) {
+        self.gray = None;
+    }
+
+    // Copy the pixels of the current subframe to the buffer.
+    fn show_gray_subframe(&mut self) {
+        if let Some(ref gray) = self.gray {
+            gray.apply(&mut self.buffer);
+            self.dirty = Some((0, BUFFER_LEN - 1));
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Show the next subframe of the image drawn with `draw_gray_temporal`,
+    /// and update the display.
+    ///
+    /// This must be called at a steady rate: the panel averages the
+    /// subframes thanks to its slow response, but a cycle slower than about
+    /// 20 Hz flickers visibly. With 8 levels, a cycle is 7 subframes, i.e. a
+    /// call every 7 ms or less. Each call sends the complete buffer.
+    ///
+    /// While drawing to the back buffer, the subframes are shown in the
+    /// presented frame instead, and only the bytes that changed are sent:
+    /// the image drawn in the back buffer appears with `present`, and the
+    /// back buffer is left untouched.
+    pub fn tick_gray(&mut self) -> Result<(), SpiE, PinE> {
+        if let Some(ref mut gray) = self.gray {
+            gray.subframe = (gray.subframe + 1) % ((1 << gray.planes.len()) - 1);
+        }
+        #[cfg(feature = "double-buffer")]
+        if self.double_buffered {
+            if let Some(ref gray) = self.gray {
+                gray.apply(&mut self.front);
+            }
+            return self.update()
+        }
+        self.show_gray_subframe();
+        self.update()
+    }
+}
+
+#[cfg(feature = "image-file")]
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Load an image file, in PNG or BMP format, and draw it as `draw_gray`
+    /// does after converting it to grayscale.
+    ///
+    /// Returns `Error::Io` if the file cannot be read, and `Error::Format`
+    /// if it cannot be decoded.
+    pub fn draw_image_file(&mut self, x : usize, y : usize, path : &Path, dither : Dither) -> Result<(), SpiE, PinE> {
+        let data = fs::read(path).map_err(Error::Io)?;
+        let img = image::load_from_memory(&data).map_err(|_| Error::Format)?.to_luma8();
+        self.draw_gray(x, y, &img, dither);
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use crate::{PCD8544, Result, BUFFER_LEN};
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Start double buffering: the display keeps showing the current frame
+    /// while the drawing functions modify the back buffer, until `present`.
+    ///
+    /// The update functions send the last presented frame instead of the
+    /// buffer, so that a half-drawn frame is never shown.
+    ///
+    /// The front buffer takes 504 bytes: this requires the `double-buffer`
+    /// feature.
+    pub fn draw_to_back(&mut self) {
+        if !self.double_buffered {
+            self.front = self.buffer;
+            self.double_buffered = true;
+        }
+    }
+
+    /// Stop double buffering: the update functions send the buffer again,
+    /// including the parts drawn since the last `present`.
+    pub fn draw_to_front(&mut self) {
+        if self.double_buffered {
+            self.double_buffered = false;
+            self.dirty = Some((0, BUFFER_LEN - 1));
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Show the content of the back buffer, sending only the bytes that
+    /// differ from the previous frame.
+    ///
+    /// Without double buffering, this is the same as `update`.
+    pub fn present(&mut self) -> Result<(), SpiE, PinE> {
+        if self.double_buffered {
+            self.front = self.buffer;
+            self.dirty = None;
+        }
+        self.update()
+    }
+}
@@ -0,0 +1,38 @@
+
+#[cfg(feature = "export-png")]
+use image::{GrayImage, ImageResult, Luma};
+#[cfg(feature = "export-png")]
+use std::path::Path;
+use crate::PCD8544;
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Render the buffer as text in the current orientation, one line per row
+    /// of pixels, with `#` where the display is dark.
+    pub fn to_ascii(&self) -> String {
+        let (width, height) = self.dimensions();
+        let mut res = String::with_capacity((width + 1) * height);
+        for y in 0..height {
+            for x in 0..width {
+                res.push(if self.pixel_bit(x, y) { '#' } else { ' ' });
+            }
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Convert the buffer to an image in the current orientation,
+    /// with black pixels where the display is dark.
+    #[cfg(feature = "export-png")]
+    pub fn to_image(&self) -> GrayImage {
+        let (width, height) = self.dimensions();
+        GrayImage::from_fn(width as u32, height as u32, |x, y| {
+            if self.pixel_bit(x as usize, y as usize) { Luma([0x00]) } else { Luma([0xFF]) }
+        })
+    }
+
+    /// Save the buffer to a PNG file.
+    #[cfg(feature = "export-png")]
+    pub fn save_png<P : AsRef<Path>>(&self, path : P) -> ImageResult<()> {
+        self.to_image().save(path)
+    }
+}
@@ -1,3 +1,20 @@
+//! Bitmap fonts.
+//!
+//! The constants spell the bits of a byte, from the most significant one,
+//! with `X` for a pixel on: they make the rows of custom glyphs readable.
+
+/// A bitmap font.
+///
+/// Each glyph is a sequence of `height()` rows of `ceil(width() / 8)` bytes,
+/// where the most significant bit of the first byte is the leftmost pixel.
+///
+/// Fonts are shared by reference between displays, possibly in different
+/// threads, so they must be `Sync`.
+pub trait Font : Sync {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn glyph(&self, c : char) -> Option<&[u8]>;
+}
 
 pub const ________ : u8 = 0x00;
 pub const _______X : u8 = 0x01;
@@ -0,0 +1,31 @@
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::BinaryColor;
+use embedded_graphics_core::Pixel;
+use core::convert::Infallible;
+use crate::PCD8544;
+
+impl<SPI, DC, RST, BL> OriginDimensions for PCD8544<SPI, DC, RST, BL> {
+    fn size(&self) -> Size {
+        let (width, height) = self.dimensions();
+        Size::new(width as u32, height as u32)
+    }
+}
+
+impl<SPI, DC, RST, BL> DrawTarget for PCD8544<SPI, DC, RST, BL> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels : I) -> Result<(), Self::Error>
+        where I : IntoIterator<Item = Pixel<Self::Color>> {
+        let size = self.size();
+        for Pixel(p, color) in pixels {
+            // Silently discard pixels that fall outside the display.
+            if p.x >= 0 && p.y >= 0 && (p.x as u32) < size.width && (p.y as u32) < size.height {
+                self.set_pixel(p.x as usize, p.y as usize, color.is_on());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+
+use crate::PCD8544;
+
+/// The width and height of the built-in icons, in pixels.
+pub const ICON_SIZE : usize = 8;
+
+/// Built-in 8x8 icons for status bars.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Icon {
+    Wifi,
+    Bluetooth,
+    /// An empty battery outline: use `draw_battery` for a charge level.
+    Battery,
+    /// Four signal bars of increasing height.
+    Signal,
+    Clock
+}
+
+impl Icon {
+    // The bitmap of the icon, as in `draw_bitmap`.
+    fn bits(self) -> &'static [u8 ; ICON_SIZE] {
+        match self {
+            Icon::Wifi      => &[0x7E, 0x81, 0x3C, 0x42, 0x18, 0x24, 0x00, 0x18],
+            Icon::Bluetooth => &[0x10, 0x18, 0x54, 0x38, 0x38, 0x54, 0x18, 0x10],
+            Icon::Battery   => &[0x00, 0xFC, 0x84, 0x86, 0x86, 0x84, 0xFC, 0x00],
+            Icon::Signal    => &[0x01, 0x01, 0x05, 0x05, 0x15, 0x15, 0x55, 0x55],
+            Icon::Clock     => &[0x3C, 0x42, 0x91, 0x91, 0x9D, 0x81, 0x42, 0x3C]
+        }
+    }
+}
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Draw an icon with its top left corner at `(x, y)`.
+    ///
+    /// The background pixels of the icon are turned off.
+    pub fn draw_icon(&mut self, x : usize, y : usize, icon : Icon) {
+        self.draw_bitmap(x, y, ICON_SIZE, ICON_SIZE, icon.bits(), false);
+    }
+}
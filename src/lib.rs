@@ -1,25 +1,118 @@
+//! Driver for the PCD8544 LCD controller (Nokia 5110 display).
+//!
+//! Without the `std` feature, the crate is `no_std`: the frame buffer and all
+//! drawing and text functions are available, as well as `from_hal` and the
+//! command functions. `reset` does not wait between the two edges of the reset
+//! pulse in this mode: use `reset_with_delay` or `from_hal_with_delay` with a
+//! delay from the HAL. The Linux backend (`new`) requires the `linux` feature.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate core;
+extern crate embedded_hal;
+#[cfg(feature = "linux")]
 extern crate sysfs_gpio;
+#[cfg(feature = "linux")]
 extern crate spidev;
+#[cfg(feature = "graphics")]
+extern crate embedded_graphics_core;
+#[cfg(any(feature = "export-png", feature = "dither"))]
+extern crate image;
+#[cfg(feature = "async")]
+extern crate tokio;
+#[cfg(feature = "serde")]
+extern crate serde;
 
-mod font;
+#[cfg(feature = "std")]
+mod animation;
+#[cfg(feature = "std")]
+mod array;
+#[cfg(feature = "async")]
+mod asynch;
+mod backlight;
+mod bitmap;
+#[cfg(feature = "std")]
+mod calibrate;
+mod codepage;
+mod config;
+#[cfg(feature = "double-buffer")]
+mod double;
+#[cfg(feature = "dither")]
+mod dither;
+#[cfg(feature = "std")]
+mod export;
+pub mod font;
+#[cfg(feature = "icons")]
+mod icons;
 mod terminus6x12;
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "linux")]
+mod linux;
+mod marquee;
+#[cfg(feature = "mock")]
+mod mock;
+mod pattern;
+#[cfg(feature = "std")]
+mod pbm;
+mod shapes;
+#[cfg(feature = "std")]
+mod sprite;
+mod text;
+mod transform;
+mod trig;
+mod widgets;
+
+#[cfg(feature = "std")]
+pub use animation::{Animation, AnimationPlayer};
+#[cfg(feature = "std")]
+pub use array::PCD8544Array;
+#[cfg(feature = "async")]
+pub use asynch::{AsyncSpiWrite, BlockingSpi};
+pub use backlight::{Backlight, NoBacklight, BacklightPin, BacklightPwm};
+pub use codepage::CodePage;
+pub use config::Config;
+#[cfg(feature = "dither")]
+pub use dither::Dither;
+pub use font::Font;
+#[cfg(feature = "icons")]
+pub use icons::{Icon, ICON_SIZE};
+pub use marquee::Marquee;
+pub use pattern::TestPattern;
+#[cfg(feature = "std")]
+pub use sprite::{Sprite, BlitMode};
+pub use terminus6x12::Terminus6x12;
+pub use text::{TextDirection, Cursor};
+pub use widgets::PlotFrame;
+#[cfg(feature = "mock")]
+pub use mock::{MockSpi, MockPin, MockPCD8544, MockResult};
+#[cfg(feature = "linux")]
+pub use linux::{SpidevBus, SysfsPin, SysfsBacklight, LinuxPCD8544, LinuxError, LinuxResult, PCD8544Builder};
+#[cfg(feature = "linux")]
+pub use linux::{SpiModeFlags, SPI_MODE_0, SPI_MODE_1, SPI_MODE_2, SPI_MODE_3};
 
-use sysfs_gpio::{Direction, Pin};
-use spidev::{Spidev, SpidevOptions, SPI_MODE_0};
-use std::io::Write;
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use core::cmp::{min, max};
+use core::fmt;
+#[cfg(feature = "std")]
 use std::thread::sleep;
+#[cfg(feature = "std")]
 use std::time::Duration;
 
 const LCDWIDTH  : usize = 84;
 const LCDHEIGHT : usize = 48;
 const ROWPIXELS : usize = LCDHEIGHT / 6;
 const BUFFER_LEN : usize = LCDWIDTH * LCDHEIGHT / 8;
+const TAB_STOP   : usize = 4;
 const DEFAULT_CONTRAST : u8 = 40;
 const DEFAULT_BIAS     : u8 = 4;
+const RESET_DELAY_MS   : u16 = 100;
+const RETRY_DELAY_MS   : u16 = 1;
 
 const PCD8544_POWERDOWN           : u8 = 0x04;
-const PCD8544_ENTRYMODE           : u8 = 0x02;
 const PCD8544_EXTENDEDINSTRUCTION : u8 = 0x01;
 const PCD8544_DISPLAYBLANK        : u8 = 0x00;
 const PCD8544_DISPLAYNORMAL       : u8 = 0x04;
@@ -33,206 +126,1090 @@ const PCD8544_SETTEMP             : u8 = 0x04;
 const PCD8544_SETBIAS             : u8 = 0x10;
 const PCD8544_SETVOP              : u8 = 0x80;
 
+/// The width of the display in pixels, in landscape orientation.
+pub const fn native_width() -> usize {
+    LCDWIDTH
+}
+
+/// The height of the display in pixels, in landscape orientation.
+pub const fn native_height() -> usize {
+    LCDHEIGHT
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Orientation {
     Portrait(bool),
     Landscape(bool)
 }
 
-pub struct PCD8544 {
-    dc : Pin,
-    rst : Pin,
-    spi : Spidev,
+impl Orientation {
+    // The logical width and height of the display in this orientation.
+    fn dimensions(self) -> (usize, usize) {
+        match self {
+            Orientation::Landscape(_) => (LCDWIDTH, LCDHEIGHT),
+            Orientation::Portrait(_)  => (LCDHEIGHT, LCDWIDTH)
+        }
+    }
+
+    // Map logical coordinates to a byte index in the buffer and a bit mask.
+    // Returns None for pixels outside the display.
+    fn location(self, x : usize, y : usize) -> Option<(usize, u8)> {
+        // Check the logical coordinates, so that the subtractions below
+        // cannot wrap around.
+        let (width, height) = self.dimensions();
+        if x >= width || y >= height {
+            return None
+        }
+
+        let (px, py) = match self {
+            Orientation::Landscape(false) => (x, y),
+            Orientation::Portrait(false)  => (LCDWIDTH - 1 - y, x),
+            Orientation::Landscape(true)  => (LCDWIDTH - 1 - x, LCDHEIGHT - 1 - y),
+            Orientation::Portrait(true)   => (y, LCDHEIGHT - 1 - x)
+        };
+
+        Some((px + (py / 8) * LCDWIDTH, 1 << (py % 8)))
+    }
+}
+
+/// An operation on a single pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelOp {
+    /// Turn the pixel on.
+    Set,
+    /// Turn the pixel off.
+    Clear,
+    /// Invert the pixel.
+    Toggle
+}
+
+/// The last command bytes sent to the controller for each setting, as
+/// decoded by the driver: the controller cannot be read back.
+///
+/// A field is `None` until the corresponding command is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct State {
+    /// Function set: power down, addressing mode and instruction set.
+    pub function_set : Option<u8>,
+    /// Display control: blank, normal, all segments on or inverted.
+    pub display_control : Option<u8>,
+    /// Set Vop: the contrast.
+    pub contrast : Option<u8>,
+    /// Bias system.
+    pub bias : Option<u8>,
+    /// Temperature control.
+    pub temperature_coefficient : Option<u8>
+}
+
+impl State {
+    // Record a command byte, in the instruction set of the last function set.
+    fn record(&mut self, c : u8) {
+        let extended = self.function_set.is_some_and(|f| f & PCD8544_EXTENDEDINSTRUCTION != 0);
+        if c & 0xF8 == PCD8544_FUNCTIONSET {
+            self.function_set = Some(c);
+        }
+        else if !extended && c & 0xF8 == PCD8544_DISPLAYCONTROL {
+            self.display_control = Some(c);
+        }
+        else if extended && c & PCD8544_SETVOP != 0 {
+            self.contrast = Some(c);
+        }
+        else if extended && c & 0xF8 == PCD8544_SETBIAS {
+            self.bias = Some(c);
+        }
+        else if extended && c & 0xFC == PCD8544_SETTEMP {
+            self.temperature_coefficient = Some(c);
+        }
+    }
+}
+
+/// A PCD8544 display and its frame buffer.
+///
+/// The display is `Send` when its bus, pins and backlight are, so that it can
+/// be moved to a rendering thread. This is the case for `LinuxPCD8544`.
+pub struct PCD8544<SPI, DC, RST, BL = NoBacklight> {
+    dc : DC,
+    rst : RST,
+    spi : SPI,
+    backlight : BL,
     buffer : [u8 ; BUFFER_LEN],
+    // The range of buffer bytes modified since the last update.
+    dirty : Option<(usize, usize)>,
+    // The content of the display memory, as last sent by update functions.
+    #[cfg(feature = "diff-update")]
+    shadow : [u8 ; BUFFER_LEN],
+    #[cfg(feature = "diff-update")]
+    shadow_valid : bool,
+    // The last frame shown with `present`, sent instead of the buffer while
+    // double buffering.
+    #[cfg(feature = "double-buffer")]
+    front : [u8 ; BUFFER_LEN],
+    #[cfg(feature = "double-buffer")]
+    double_buffered : bool,
+    contrast : u8,
+    bias : u8,
+    temp_coef : u8,
+    display_mode : u8,
+    max_transfer : usize,
+    write_attempts : u32,
     pub orient : Orientation,
     pub char_spacing : usize,
-    pub inverse : bool
+    pub inverse : bool,
+    font : &'static dyn Font,
+    codepage : CodePage,
+    proportional : bool,
+    direction : TextDirection,
+    // The text position used by the fmt::Write implementation.
+    cursor : Cursor,
+    state : State,
+    // The bit planes of the grayscale image shown by `tick_gray`.
+    #[cfg(feature = "dither")]
+    gray : Option<Box<dither::GrayPlanes>>
 }
 
+/// The errors of the driver.
+///
+/// Some variants only exist with some features, such as `Io` with `std`:
+/// matches on this type need a wildcard arm.
 #[derive(Debug)]
-pub enum Error {
-    PinError(sysfs_gpio::Error),
-    SpiDevError(std::io::Error)
+#[non_exhaustive]
+pub enum Error<SpiE, PinE> {
+    PinError(PinE),
+    SpiDevError(SpiE),
+    /// Malformed image data.
+    Format,
+    /// A value out of the range accepted by the controller.
+    InvalidParameter,
+    /// A buffer that does not have the size of the display memory.
+    InvalidBufferSize,
+    /// A file that could not be read.
+    #[cfg(feature = "std")]
+    Io(std::io::Error)
 }
 
-impl From<sysfs_gpio::Error> for Error {
-    fn from(e : sysfs_gpio::Error) -> Error {
-        Error::PinError(e)
+impl<SpiE : fmt::Display, PinE : fmt::Display> fmt::Display for Error<SpiE, PinE> {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::PinError(ref e)    => write!(f, "GPIO pin error: {}", e),
+            Error::SpiDevError(ref e) => write!(f, "SPI write failed: {}", e),
+            Error::Format             => write!(f, "invalid image format"),
+            Error::InvalidParameter   => write!(f, "parameter out of range"),
+            Error::InvalidBufferSize  => write!(f, "invalid buffer size"),
+            #[cfg(feature = "std")]
+            Error::Io(ref e)          => write!(f, "I/O error: {}", e)
+        }
     }
 }
 
-impl From<std::io::Error> for Error {
-    fn from(e : std::io::Error) -> Error {
-        Error::SpiDevError(e)
+#[cfg(feature = "std")]
+impl<SpiE, PinE> std::error::Error for Error<SpiE, PinE>
+    where SpiE : std::error::Error + 'static,
+          PinE : std::error::Error + 'static {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::PinError(ref e)    => Some(e),
+            Error::SpiDevError(ref e) => Some(e),
+            Error::Format             => None,
+            Error::InvalidParameter   => None,
+            Error::InvalidBufferSize  => None,
+            Error::Io(ref e)          => Some(e)
+        }
     }
 }
 
-type Result<T> = std::result::Result<T, Error>;
+/// The result of the operations that access the hardware.
+pub type Result<T, SpiE, PinE> = core::result::Result<T, Error<SpiE, PinE>>;
 
-fn new_pin(n : u64, dir : Direction, timeout : Duration, retries : u32) -> Result<Pin> {
-    let pin = Pin::new(n);
+impl<SPI, DC, RST, SpiE, PinE> PCD8544<SPI, DC, RST>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
 
-    // Assume the pin will be correctly configured.
-    let mut res : Result<Pin> = Ok(pin);
+    pub fn from_hal(spi : SPI, dc : DC, rst : RST) -> Result<Self, SpiE, PinE> {
+        let mut res = Self::with_hal(spi, dc, rst, NoBacklight);
+        res.init(DEFAULT_CONTRAST, DEFAULT_BIAS)?;
+        Ok(res)
+    }
 
-    // Export the sysfs entry for the chosen pin.
-    pin.export()?;
+    /// Same as `from_hal`, using the given delay during the reset pulse.
+    pub fn from_hal_with_delay<D : DelayMs<u16>>(spi : SPI, dc : DC, rst : RST, delay : &mut D) -> Result<Self, SpiE, PinE> {
+        let mut res = Self::with_hal(spi, dc, rst, NoBacklight);
+        res.init_with_delay(DEFAULT_CONTRAST, DEFAULT_BIAS, delay)?;
+        Ok(res)
+    }
+}
 
-    // The sysfs entry might not be immediately usable
-    // after the export operation.
-    // We will call set_direction() repeatedly until the operation completes
-    // or after a given number of attempts.
-    for k in 0..retries {
-        if k > 0 {
-            sleep(timeout);
-        }
-        match pin.set_direction(dir) {
-            Ok(_)  => return Ok(pin),
-            Err(e) => res = Err(Error::from(e))
-        }
+// The delay used by `reset`: a sleep with std, nothing otherwise.
+struct DefaultDelay;
+
+impl DelayMs<u16> for DefaultDelay {
+    #[cfg(feature = "std")]
+    fn delay_ms(&mut self, ms : u16) {
+        sleep(Duration::from_millis(ms as u64));
     }
 
-    // Return the last result.
+    #[cfg(not(feature = "std"))]
+    fn delay_ms(&mut self, _ : u16) {}
+}
+
+// Write to the bus, making up to `attempts` attempts and returning the
+// last error.
+fn write_with_retries<SPI : spi::Write<u8>>(spi : &mut SPI, words : &[u8], attempts : u32) -> core::result::Result<(), SPI::Error> {
+    let mut res = spi.write(words);
+    for _ in 1..attempts {
+        if res.is_ok() {
+            break;
+        }
+        DefaultDelay.delay_ms(RETRY_DELAY_MS);
+        res = spi.write(words);
+    }
     res
 }
 
-impl PCD8544 {
-    pub fn new(dc : u64, rst : u64, spi : &str, orient : Orientation) -> Result<Self> {
-        let mut spidev = Spidev::open(spi)?;
-        let mut options = SpidevOptions::new();
-        options.bits_per_word(8).max_speed_hz(4_000_000).mode(SPI_MODE_0);
-        spidev.configure(&options)?;
+// The commands moving the address of the display memory to a buffer index.
+pub(crate) fn address_commands(index : usize) -> [u8 ; 2] {
+    [PCD8544_SETYADDR | (index / LCDWIDTH) as u8, PCD8544_SETXADDR | (index % LCDWIDTH) as u8]
+}
 
-        let mut res = Self {
-            dc  : new_pin(dc,  Direction::Out, Duration::from_millis(100), 3)?,
-            rst : new_pin(rst, Direction::Out, Duration::from_millis(100), 3)?,
-            spi : spidev,
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    // Create a driver without sending any command to the controller.
+    fn with_hal(spi : SPI, dc : DC, rst : RST, backlight : BL) -> Self {
+        Self {
+            dc,
+            rst,
+            spi,
+            backlight,
             buffer : [0x00 ; BUFFER_LEN],
-            orient : orient,
+            // The content of the display memory is unknown.
+            dirty : Some((0, BUFFER_LEN - 1)),
+            #[cfg(feature = "diff-update")]
+            shadow : [0x00 ; BUFFER_LEN],
+            #[cfg(feature = "diff-update")]
+            shadow_valid : false,
+            #[cfg(feature = "double-buffer")]
+            front : [0x00 ; BUFFER_LEN],
+            #[cfg(feature = "double-buffer")]
+            double_buffered : false,
+            contrast : DEFAULT_CONTRAST,
+            bias : DEFAULT_BIAS,
+            temp_coef : 0,
+            display_mode : PCD8544_DISPLAYNORMAL,
+            max_transfer : BUFFER_LEN,
+            write_attempts : 1,
+            orient : Orientation::Landscape(false),
             char_spacing : 0,
-            inverse : false
-        };
+            inverse : false,
+            font : &Terminus6x12,
+            codepage : CodePage::Latin1,
+            proportional : false,
+            direction : TextDirection::LeftRight,
+            cursor : Cursor::default(),
+            state : State::default(),
+            #[cfg(feature = "dither")]
+            gray : None
+        }
+    }
 
-        res.reset()?;
-        res.set_contrast(DEFAULT_CONTRAST)?;
-        res.set_bias(DEFAULT_BIAS)?;
+    // Reset the controller and set the initial contrast and bias.
+    fn init(&mut self, contrast : u8, bias : u8) -> Result<(), SpiE, PinE> {
+        self.init_with_delay(contrast, bias, &mut DefaultDelay)
+    }
 
-        Ok(res)
+    fn init_with_delay<D : DelayMs<u16>>(&mut self, contrast : u8, bias : u8, delay : &mut D) -> Result<(), SpiE, PinE> {
+        self.reset_with_delay(delay)?;
+        self.set_contrast(contrast)?;
+        self.set_bias(bias)?;
+        Ok(())
     }
 
-    pub fn reset(&mut self) -> Result<()> {
-        self.rst.set_value(0)?;
-        sleep(Duration::from_millis(100));
-        self.rst.set_value(1)?;
+    /// Send a list of command bytes, in order.
+    ///
+    /// This allows custom initialization sequences, after `reset`. The
+    /// extended commands must be preceded by a function set command with
+    /// the extended instruction set, as in the datasheet. The values returned
+    /// by `contrast` and `bias` are not updated.
+    pub fn init_with(&mut self, commands : &[u8]) -> Result<(), SpiE, PinE> {
+        for &c in commands {
+            self.send_command(c)?;
+        }
         Ok(())
     }
 
-    pub fn send_command(&mut self, c : u8) -> Result<()> {
-        self.dc.set_value(0)?;
-        self.spi.write(&[c])?;
+    /// Reset the controller, holding the reset pin low for 100 ms with std.
+    ///
+    /// Without std, this does not wait: use `reset_with_delay` instead.
+    pub fn reset(&mut self) -> Result<(), SpiE, PinE> {
+        self.reset_with_delay(&mut DefaultDelay)
+    }
+
+    /// Reset the controller, using the given delay to hold the reset pin low.
+    pub fn reset_with_delay<D : DelayMs<u16>>(&mut self, delay : &mut D) -> Result<(), SpiE, PinE> {
+        self.rst.set_low().map_err(Error::PinError)?;
+        delay.delay_ms(RESET_DELAY_MS);
+        self.rst.set_high().map_err(Error::PinError)?;
+        // The display memory is lost: the next update sends the whole buffer.
+        self.forget_display_memory();
+        self.dirty = Some((0, BUFFER_LEN - 1));
         Ok(())
     }
 
-    pub fn send_extended_command(&mut self, c : u8) -> Result<()> {
+    pub fn send_command(&mut self, c : u8) -> Result<(), SpiE, PinE> {
+        self.dc.set_low().map_err(Error::PinError)?;
+        write_with_retries(&mut self.spi, &[c], self.write_attempts).map_err(Error::SpiDevError)?;
+        self.state.record(c);
+        Ok(())
+    }
+
+    pub fn send_extended_command(&mut self, c : u8) -> Result<(), SpiE, PinE> {
         // Set extended command mode
         self.send_command(PCD8544_FUNCTIONSET | PCD8544_EXTENDEDINSTRUCTION)?;
         self.send_command(c)?;
-        // Set normal display mode.
+        // Restore basic commands and the current display mode.
         self.send_command(PCD8544_FUNCTIONSET)?;
-        self.send_command(PCD8544_DISPLAYCONTROL | PCD8544_DISPLAYNORMAL)?;
+        self.send_command(PCD8544_DISPLAYCONTROL | self.display_mode)?;
         Ok(())
     }
 
-    pub fn send_data_byte(&mut self, c : u8) -> Result<()> {
-        self.dc.set_value(1)?;
-        self.spi.write(&[c])?;
+    pub fn send_data_byte(&mut self, c : u8) -> Result<(), SpiE, PinE> {
+        // The byte bypasses the buffer and the copy of the display memory.
+        self.forget_display_memory();
+        self.dc.set_high().map_err(Error::PinError)?;
+        write_with_retries(&mut self.spi, &[c], self.write_attempts).map_err(Error::SpiDevError)?;
         Ok(())
     }
 
-    pub fn set_contrast(&mut self, contrast : u8) -> Result<()> {
+    /// Set the address in the display memory of the next data byte.
+    ///
+    /// The address is in native landscape coordinates: `x` is the column,
+    /// from 0 to 83, and `y` is the row of bytes, from 0 to 5, each byte
+    /// holding 8 pixel rows with the top one in the least significant bit.
+    /// The address advances after each byte sent with `send_data_byte`, from
+    /// left to right and then to the next row of bytes.
+    ///
+    /// Returns `Error::InvalidParameter` for an address outside the display
+    /// memory. As the bytes sent this way bypass the buffer, the next
+    /// `update_diff` sends the whole buffer.
+    pub fn set_address(&mut self, x : u8, y : u8) -> Result<(), SpiE, PinE> {
+        if x as usize >= LCDWIDTH || y as usize >= LCDHEIGHT / ROWPIXELS {
+            return Err(Error::InvalidParameter)
+        }
+        self.forget_display_memory();
+        self.send_command(PCD8544_SETYADDR | y)?;
+        self.send_command(PCD8544_SETXADDR | x)?;
+        Ok(())
+    }
+
+    /// Set the operating voltage of the LCD, from 0 to 127.
+    ///
+    /// Larger values are clamped to 127. See `try_set_contrast` for a version
+    /// that reports them as errors.
+    pub fn set_contrast(&mut self, contrast : u8) -> Result<(), SpiE, PinE> {
         let mut c = contrast;
         if c > 127 {
             c = 127;
         }
         self.send_extended_command(PCD8544_SETVOP | c)?;
+        self.contrast = c;
         Ok(())
     }
 
-    pub fn set_bias(&mut self, bias : u8) -> Result<()> {
+    /// Set the operating voltage of the LCD, from 0 to 127.
+    ///
+    /// Unlike `set_contrast`, this returns `Error::InvalidParameter` for
+    /// larger values, without sending anything to the controller.
+    pub fn try_set_contrast(&mut self, contrast : u8) -> Result<(), SpiE, PinE> {
+        if contrast > 127 {
+            return Err(Error::InvalidParameter)
+        }
+        self.set_contrast(contrast)
+    }
+
+    /// Set the bias system of the LCD driver, from 0 to 7.
+    ///
+    /// The recommended value for the Nokia 5110 display, with its typical
+    /// voltage multiplier, is 4 (1:48 multiplex rate).
+    pub fn set_bias(&mut self, bias : u8) -> Result<(), SpiE, PinE> {
+        if bias > 7 {
+            return Err(Error::InvalidParameter)
+        }
         self.send_extended_command(PCD8544_SETBIAS | bias)?;
+        self.bias = bias;
+        Ok(())
+    }
+
+    /// Set the temperature coefficient of the LCD supply voltage, from 0 to 3.
+    ///
+    /// Higher coefficients compensate for the contrast drift at low temperatures.
+    pub fn set_temperature_coefficient(&mut self, tc : u8) -> Result<(), SpiE, PinE> {
+        if tc > 3 {
+            return Err(Error::InvalidParameter)
+        }
+        self.send_extended_command(PCD8544_SETTEMP | tc)?;
+        self.temp_coef = tc;
+        Ok(())
+    }
+
+    /// Invert the display in the controller, without modifying the buffer.
+    ///
+    /// Unlike the `inverse` field, which applies when drawing pixels,
+    /// this applies immediately to the whole display.
+    pub fn set_display_inverted(&mut self, inverted : bool) -> Result<(), SpiE, PinE> {
+        let mode = if inverted { PCD8544_DISPLAYINVERTED } else { PCD8544_DISPLAYNORMAL };
+        self.set_display_mode(mode)
+    }
+
+    /// Turn all segments on, whatever the content of the display memory.
+    pub fn all_segments_on(&mut self) -> Result<(), SpiE, PinE> {
+        self.set_display_mode(PCD8544_DISPLAYALLON)
+    }
+
+    /// Turn all segments off, whatever the content of the display memory.
+    pub fn blank(&mut self) -> Result<(), SpiE, PinE> {
+        self.set_display_mode(PCD8544_DISPLAYBLANK)
+    }
+
+    /// Show the content of the display memory again.
+    pub fn display_normal(&mut self) -> Result<(), SpiE, PinE> {
+        self.set_display_mode(PCD8544_DISPLAYNORMAL)
+    }
+
+    fn set_display_mode(&mut self, mode : u8) -> Result<(), SpiE, PinE> {
+        self.send_command(PCD8544_DISPLAYCONTROL | mode)?;
+        self.display_mode = mode;
+        Ok(())
+    }
+
+    /// Put the controller in power-down mode.
+    pub fn power_down(&mut self) -> Result<(), SpiE, PinE> {
+        self.send_command(PCD8544_FUNCTIONSET | PCD8544_POWERDOWN)
+    }
+
+    /// Wake the controller up, restoring the last contrast and bias.
+    ///
+    /// The content of the display memory is not guaranteed after a
+    /// power-down: the next update sends the complete frame, including
+    /// `update_diff` and the updates while double buffering.
+    pub fn power_up(&mut self) -> Result<(), SpiE, PinE> {
+        self.send_command(PCD8544_FUNCTIONSET)?;
+        self.send_command(PCD8544_DISPLAYCONTROL | self.display_mode)?;
+        let (contrast, bias, tc) = (self.contrast, self.bias, self.temp_coef);
+        self.set_contrast(contrast)?;
+        self.set_bias(bias)?;
+        self.set_temperature_coefficient(tc)?;
+        self.forget_display_memory();
+        self.dirty = Some((0, BUFFER_LEN - 1));
+        Ok(())
+    }
+
+    /// Send the bytes of the buffer that changed since the last update.
+    ///
+    /// While drawing to the back buffer, this only sends the parts of the
+    /// last presented frame that the display does not show.
+    pub fn update(&mut self) -> Result<(), SpiE, PinE> {
+        #[cfg(feature = "double-buffer")]
+        if self.double_buffered {
+            return self.update_diff()
+        }
+        if let Some((start, end)) = self.dirty {
+            // Move to the first modified byte.
+            // The controller address wraps to the next row automatically.
+            self.send_address(start)?;
+            // Write the modified part of the buffer.
+            self.send_buffer(start, end + 1)?;
+            self.dirty = None;
+        }
         Ok(())
     }
 
-    pub fn update(&mut self) -> Result<()> {
-        // TODO: Consider support for partial updates like Arduino library.
-        // Reset to position zero.
-        self.send_command(PCD8544_SETYADDR)?;
-        self.send_command(PCD8544_SETXADDR)?;
-        // Write the buffer.
-        self.dc.set_value(1)?;
-        self.spi.write(&self.buffer)?;
+    /// Send the complete buffer to the display.
+    pub fn update_full(&mut self) -> Result<(), SpiE, PinE> {
+        #[cfg(feature = "double-buffer")]
+        if self.double_buffered {
+            // Send the last presented frame, keeping the dirty range of the
+            // back buffer.
+            self.send_command(PCD8544_SETYADDR)?;
+            self.send_command(PCD8544_SETXADDR)?;
+            return self.send_buffer(0, BUFFER_LEN)
+        }
+        self.dirty = Some((0, BUFFER_LEN - 1));
+        self.update()
+    }
+
+    /// Send only the bytes that differ from the content of the display memory.
+    ///
+    /// The driver keeps a copy of the data sent to the display. This function
+    /// compares it with the buffer and sends each changed range separately.
+    /// It falls back to a full write when more than 60% of the bytes changed,
+    /// or when the content of the display memory is unknown, as after a reset.
+    ///
+    /// The copy of the display memory takes 504 bytes: this requires the
+    /// `diff-update` feature.
+    #[cfg(feature = "diff-update")]
+    pub fn update_diff(&mut self) -> Result<(), SpiE, PinE> {
+        if self.needs_full_update() {
+            return self.update_full()
+        }
+
+        let mut index = 0;
+        while let Some((start, end)) = self.next_changed_range(index) {
+            self.send_address(start)?;
+            self.send_buffer(start, end)?;
+            index = end;
+        }
+        // While double buffering, the dirty range is the one of the back buffer.
+        #[cfg(feature = "double-buffer")]
+        if self.double_buffered {
+            return Ok(())
+        }
+        self.dirty = None;
+        Ok(())
+    }
+
+    // Move the address of the display memory to a buffer index.
+    fn send_address(&mut self, index : usize) -> Result<(), SpiE, PinE> {
+        for c in address_commands(index).iter() {
+            self.send_command(*c)?;
+        }
+        Ok(())
+    }
+
+    // Send a range of the buffer as data, in transfers of at most
+    // max_transfer bytes.
+    fn send_buffer(&mut self, start : usize, end : usize) -> Result<(), SpiE, PinE> {
+        self.dc.set_high().map_err(Error::PinError)?;
+        let attempts = self.write_attempts;
+        let mut pos = start;
+        while pos < end {
+            let next = min(pos + self.max_transfer, end);
+            let (frame, spi) = self.frame_and_bus();
+            write_with_retries(spi, &frame[pos .. next], attempts).map_err(Error::SpiDevError)?;
+            pos = next;
+        }
+        #[cfg(feature = "diff-update")]
+        self.record_sent(start, end);
+        Ok(())
+    }
+
+    /// Send a rectangular region of the buffer to the display.
+    ///
+    /// The region is given in native landscape coordinates, whatever the
+    /// current orientation. It is clipped to the display and extended
+    /// vertically to the 8-pixel rows of the controller memory.
+    pub fn update_region(&mut self, x : usize, y : usize, w : usize, h : usize) -> Result<(), SpiE, PinE> {
+        let x1 = min(x.saturating_add(w), LCDWIDTH);
+        let y1 = min(y.saturating_add(h), LCDHEIGHT);
+        if x >= x1 || y >= y1 {
+            return Ok(())
+        }
+
+        for row in y / ROWPIXELS .. (y1 - 1) / ROWPIXELS + 1 {
+            self.send_command(PCD8544_SETYADDR | row as u8)?;
+            self.send_command(PCD8544_SETXADDR | x as u8)?;
+            self.send_buffer(row * LCDWIDTH + x, row * LCDWIDTH + x1)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the content of the buffer.
+    ///
+    /// `data` uses the layout of the display memory: 6 rows of 84 bytes,
+    /// where each byte is a column of 8 pixels with the top pixel in the
+    /// least significant bit.
+    pub fn set_buffer(&mut self, data : &[u8]) -> Result<(), SpiE, PinE> {
+        if data.len() != BUFFER_LEN {
+            return Err(Error::InvalidBufferSize)
+        }
+        self.buffer.copy_from_slice(data);
+        self.dirty = Some((0, BUFFER_LEN - 1));
         Ok(())
     }
+}
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+
+    /// Attach a backlight to the display.
+    pub fn with_backlight<B : Backlight>(self, backlight : B) -> PCD8544<SPI, DC, RST, B> {
+        PCD8544 {
+            dc : self.dc,
+            rst : self.rst,
+            spi : self.spi,
+            backlight,
+            buffer : self.buffer,
+            dirty : self.dirty,
+            #[cfg(feature = "diff-update")]
+            shadow : self.shadow,
+            #[cfg(feature = "diff-update")]
+            shadow_valid : self.shadow_valid,
+            #[cfg(feature = "double-buffer")]
+            front : self.front,
+            #[cfg(feature = "double-buffer")]
+            double_buffered : self.double_buffered,
+            contrast : self.contrast,
+            bias : self.bias,
+            temp_coef : self.temp_coef,
+            display_mode : self.display_mode,
+            max_transfer : self.max_transfer,
+            write_attempts : self.write_attempts,
+            orient : self.orient,
+            char_spacing : self.char_spacing,
+            inverse : self.inverse,
+            font : self.font,
+            codepage : self.codepage,
+            proportional : self.proportional,
+            direction : self.direction,
+            cursor : self.cursor,
+            state : self.state,
+            #[cfg(feature = "dither")]
+            gray : self.gray
+        }
+    }
+
+    /// The last contrast value sent to the controller.
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// The last bias value sent to the controller.
+    pub fn bias(&self) -> u8 {
+        self.bias
+    }
+
+    /// Limit the size of SPI writes, for drivers that reject long transfers.
+    ///
+    /// By default, the complete buffer is sent in a single write.
+    pub fn set_max_transfer_bytes(&mut self, n : usize) {
+        self.max_transfer = max(n, 1);
+    }
+
+    /// The number of attempts for each SPI write, with a 1 ms delay between
+    /// two attempts when `std` is enabled.
+    ///
+    /// By default, a failed write is reported without retrying.
+    pub fn set_spi_write_retries(&mut self, attempts : u32) {
+        self.write_attempts = max(attempts, 1);
+    }
+
+    /// The content of the buffer, in the layout of the display memory.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Direct access to the 504 bytes of the buffer, for custom rendering.
+    ///
+    /// The layout is the one of the display memory in native landscape
+    /// coordinates: 6 bands of 8 pixel rows, each made of 84 bytes from left
+    /// to right. The pixel (x, y) is bit `y % 8` of byte `x + (y / 8) * 84`,
+    /// with the top pixel of each byte in the least significant bit. The
+    /// orientation and `inverse` are not applied.
+    ///
+    /// The whole buffer is marked as modified, so that the next `update`
+    /// sends it completely.
+    pub fn buffer_mut(&mut self) -> &mut [u8 ; BUFFER_LEN] {
+        self.dirty = Some((0, BUFFER_LEN - 1));
+        &mut self.buffer
+    }
+
+    /// A copy of the buffer, to be given back to `restore`.
+    pub fn snapshot(&self) -> [u8 ; BUFFER_LEN] {
+        self.buffer
+    }
+
+    /// Replace the buffer with a copy returned by `snapshot`.
+    ///
+    /// Only the bytes that differ from the current buffer are marked as
+    /// modified, so removing an overlay sends just the area it covered.
+    pub fn restore(&mut self, snap : &[u8 ; BUFFER_LEN]) {
+        for (index, &b) in snap.iter().enumerate() {
+            if self.buffer[index] != b {
+                self.buffer[index] = b;
+                self.mark_dirty(index);
+            }
+        }
+    }
+
+    // The frame sent by the update functions, and the bus to send it.
+    pub(crate) fn frame_and_bus(&mut self) -> (&[u8 ; BUFFER_LEN], &mut SPI) {
+        #[cfg(feature = "double-buffer")]
+        let frame = if self.double_buffered { &self.front } else { &self.buffer };
+        #[cfg(not(feature = "double-buffer"))]
+        let frame = &self.buffer;
+        (frame, &mut self.spi)
+    }
+
+    // Copy a range of the frame that was sent to the copy of the display
+    // memory.
+    #[cfg(feature = "diff-update")]
+    pub(crate) fn record_sent(&mut self, start : usize, end : usize) {
+        #[cfg(feature = "double-buffer")]
+        let frame = if self.double_buffered { &self.front } else { &self.buffer };
+        #[cfg(not(feature = "double-buffer"))]
+        let frame = &self.buffer;
+        self.shadow[start .. end].copy_from_slice(&frame[start .. end]);
+        if start == 0 && end == BUFFER_LEN {
+            self.shadow_valid = true;
+        }
+    }
+
+    // Whether update_diff sends the whole frame: when the content of the
+    // display memory is unknown, or when more than 60% of the bytes changed.
+    #[cfg(feature = "diff-update")]
+    pub(crate) fn needs_full_update(&self) -> bool {
+        let frame = self.frame();
+        let changed = frame.iter().zip(self.shadow.iter()).filter(|&(a, b)| a != b).count();
+        !self.shadow_valid || changed * 10 > BUFFER_LEN * 6
+    }
+
+    // The next range of the frame, from the given index, that differs from
+    // the display memory. The range is extended over gaps shorter than the
+    // cost of setting the address.
+    #[cfg(feature = "diff-update")]
+    pub(crate) fn next_changed_range(&self, from : usize) -> Option<(usize, usize)> {
+        let frame = self.frame();
+        let start = (from .. BUFFER_LEN).find(|&k| frame[k] != self.shadow[k])?;
+        let mut end = start + 1;
+        while end < BUFFER_LEN {
+            match (end .. min(end + 3, BUFFER_LEN)).find(|&k| frame[k] != self.shadow[k]) {
+                Some(k) => end = k + 1,
+                None    => break
+            }
+        }
+        Some((start, end))
+    }
+
+    // The frame sent by the update functions.
+    #[cfg(feature = "diff-update")]
+    fn frame(&self) -> &[u8 ; BUFFER_LEN] {
+        #[cfg(feature = "double-buffer")]
+        let frame = if self.double_buffered { &self.front } else { &self.buffer };
+        #[cfg(not(feature = "double-buffer"))]
+        let frame = &self.buffer;
+        frame
+    }
+
+    // Forget the copy of the display memory, after the controller lost it
+    // or received data that bypassed the buffer.
+    fn forget_display_memory(&mut self) {
+        #[cfg(feature = "diff-update")]
+        {
+            self.shadow_valid = false;
+        }
+    }
 
     pub fn clear(&mut self) {
-        self.buffer = [0x00 ; BUFFER_LEN]
+        self.buffer = [0x00 ; BUFFER_LEN];
+        self.dirty = Some((0, BUFFER_LEN - 1));
     }
 
-    pub fn set_pixel(&mut self, x : usize, y : usize, value : bool) {
-        let (px, py) = match self.orient {
-            Orientation::Landscape(false) => (x, y),
-            Orientation::Portrait(false)  => (LCDWIDTH - 1 - y, x),
-            Orientation::Landscape(true)  => (y, LCDHEIGHT - 1 - x),
-            Orientation::Portrait(true)   => (LCDWIDTH - 1 - x, LCDHEIGHT - 1 - y)
+    /// The last commands sent to the controller, for diagnostics.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Whether the buffer was modified since the last update, or since the
+    /// last `present` while drawing to the back buffer.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.is_some()
+    }
+
+    // Extend the dirty range to include the given buffer index.
+    fn mark_dirty(&mut self, index : usize) {
+        self.dirty = match self.dirty {
+            Some((start, end)) => Some((min(start, index), max(end, index))),
+            None               => Some((index, index))
         };
+    }
+
+    /// The width of the display in pixels, in the current orientation.
+    pub fn width(&self) -> usize {
+        self.dimensions().0
+    }
+
+    /// The height of the display in pixels, in the current orientation.
+    pub fn height(&self) -> usize {
+        self.dimensions().1
+    }
+
+    /// The number of character columns that fit in the width of the display,
+    /// with the current font, spacing and orientation.
+    pub fn text_cols(&self) -> usize {
+        self.dimensions().0 / (self.font.width() + self.char_spacing)
+    }
+
+    /// The number of text rows that fit in the height of the display, with
+    /// the current font and orientation.
+    pub fn text_rows(&self) -> usize {
+        self.dimensions().1 / self.font.height()
+    }
+
+    // The logical width and height of the display in the current orientation.
+    fn dimensions(&self) -> (usize, usize) {
+        self.orient.dimensions()
+    }
+
+    // Map logical coordinates to a byte index in the buffer and a bit mask.
+    // Returns None for pixels outside the display.
+    fn pixel_location(&self, x : usize, y : usize) -> Option<(usize, u8)> {
+        self.orient.location(x, y)
+    }
 
-        if px >= LCDWIDTH || py >= LCDHEIGHT {
-            return
+    pub fn set_pixel(&mut self, x : usize, y : usize, value : bool) {
+        if let Some((index, bv)) = self.pixel_location(x, y) {
+            self.mark_dirty(index);
+            if value != self.inverse {
+                self.buffer[index] |= bv;
+            }
+            else {
+                self.buffer[index] &= !bv;
+            }
         }
+    }
 
-        let bv : u8 = 1 << (py % 8);
+    pub fn toggle_pixel(&mut self, x : usize, y : usize) {
+        if let Some((index, bv)) = self.pixel_location(x, y) {
+            self.mark_dirty(index);
+            self.buffer[index] ^= bv;
+        }
+    }
 
-        if value != self.inverse {
-            self.buffer[px + (py / 8) * LCDWIDTH] |= bv;
+    /// Apply an operation to a pixel.
+    pub fn set_pixel_op(&mut self, x : usize, y : usize, op : PixelOp) {
+        match op {
+            PixelOp::Set    => self.set_pixel(x, y, true),
+            PixelOp::Clear  => self.set_pixel(x, y, false),
+            PixelOp::Toggle => self.toggle_pixel(x, y)
         }
-        else {
-            self.buffer[px + (py / 8) * LCDWIDTH] &= !bv;
+    }
+
+    pub fn get_pixel(&self, x : usize, y : usize) -> bool {
+        match self.pixel_location(x, y) {
+            Some((index, bv)) => ((self.buffer[index] & bv) != 0x00) != self.inverse,
+            None              => false
         }
     }
 
-    pub fn print_char(&mut self, x : usize, y : usize, c : char) {
-        // Get the index of the current character in the font.
-        let index = match terminus6x12::ENCODING.iter().position(|&v| v == c as u16) {
-            Some(k) => k,
-            None    => 0xFFFD
+    // Whether the bit of a pixel is set in the buffer, ignoring `inverse`.
+    #[cfg(feature = "std")]
+    fn pixel_bit(&self, x : usize, y : usize) -> bool {
+        match self.pixel_location(x, y) {
+            Some((index, bv)) => (self.buffer[index] & bv) != 0x00,
+            None              => false
+        }
+    }
+
+    pub fn set_font(&mut self, font : &'static dyn Font) {
+        self.font = font;
+    }
+
+    /// Set the code page used to print the characters U+0080 to U+00FF.
+    ///
+    /// Characters missing from the font are printed as U+FFFD if the font
+    /// has it, or as blank cells.
+    pub fn set_codepage(&mut self, cp : CodePage) {
+        self.codepage = cp;
+    }
+
+    /// Advance each character by the width of its glyph, without the blank
+    /// columns on its right, plus one pixel, instead of a fixed width.
+    ///
+    /// This applies to `print`, `print_scaled`, `print_centered`,
+    /// `print_right` and `measure_text`. A blank glyph, such as a space,
+    /// is half the width of the font. Character coordinates, and the text
+    /// cursor, still refer to the fixed-width grid.
+    pub fn set_proportional(&mut self, on : bool) {
+        self.proportional = on;
+    }
+
+    // The horizontal advance of a character, in font pixels.
+    fn glyph_advance(&self, c : char) -> usize {
+        let font = self.font;
+        if !self.proportional {
+            return font.width() + self.char_spacing
+        }
+        // The rightmost column with a pixel on, plus one.
+        let row_bytes = font.width().div_ceil(8);
+        let ink = match font.glyph(self.codepage.map(c)).or_else(|| font.glyph('\u{FFFD}')) {
+            Some(g) => g.iter().enumerate()
+                .filter(|&(_, &b)| b != 0x00)
+                .map(|(i, &b)| (i % row_bytes) * 8 + 8 - b.trailing_zeros() as usize)
+                .max().unwrap_or(0),
+            None    => 0
         };
+        if ink == 0 {
+            font.width() / 2
+        }
+        else {
+            min(ink, font.width()) + 1
+        }
+    }
 
+    /// Draw a character on the character grid, with `x` and `y` in cells.
+    ///
+    /// Use `draw_char_at` to place a character by pixel.
+    pub fn print_char(&mut self, x : usize, y : usize, c : char) {
         // Convert character coordinates to pixels.
-        let xp = x * (terminus6x12::WIDTH + self.char_spacing);
-        let yp = y * terminus6x12::HEIGHT;
-
-        for r in 0..terminus6x12::HEIGHT {
-            let b = terminus6x12::BITMAP[r + index * terminus6x12::HEIGHT];
-            let mut m = 0x80;
-            for k in 0..8 {
-                self.set_pixel(xp + k, yp + r, (b & m) != 0x00);
-                m >>= 1;
+        let xp = x * (self.font.width() + self.char_spacing);
+        let yp = y * self.font.height();
+        self.draw_char_at(xp, yp, c, 1);
+    }
+
+    /// Draw a character with its top left corner at the given pixel
+    /// coordinates, each font pixel being scaled to a square block.
+    ///
+    /// The whole glyph cell is drawn, so the background pixels are turned off.
+    pub fn draw_char_at(&mut self, xp : usize, yp : usize, c : char, scale : usize) {
+        self.draw_char_clipped(xp as isize, yp as isize, c, scale, usize::MAX);
+    }
+
+    // Same as draw_char_at, skipping the pixels above and left of the
+    // display, and the columns from `right` on.
+    fn draw_char_clipped(&mut self, xp : isize, yp : isize, c : char, scale : usize, right : usize) {
+        // Get the bitmap of the current character in the font,
+        // falling back to the replacement character.
+        let font = self.font;
+        let glyph = font.glyph(self.codepage.map(c)).or_else(|| font.glyph('\u{FFFD}'));
+
+        let row_bytes = font.width().div_ceil(8);
+        for r in 0..font.height() {
+            for k in 0..font.width() {
+                // Draw a blank cell if the font has no replacement character.
+                let b = match glyph {
+                    Some(g) => g.get(r * row_bytes + k / 8).cloned().unwrap_or(0x00),
+                    None    => 0x00
+                };
+                let value = (b & (0x80 >> (k % 8))) != 0x00;
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = xp + (k * scale + dx) as isize;
+                        let py = yp + (r * scale + dy) as isize;
+                        if px >= 0 && (px as usize) < right && py >= 0 {
+                            self.set_pixel(px as usize, py as usize, value);
+                        }
+                    }
+                }
             }
         }
     }
 
     pub fn print(&mut self, x : usize, y : usize, s : &str) {
-        let mut xc = x;
+        self.print_scaled(x, y, s, 1);
+    }
+
+    pub fn print_scaled(&mut self, x : usize, y : usize, s : &str, scale : usize) {
+        self.print_from(x, y, x, s, scale, false);
+    }
+
+    // Print a string from the given character coordinates in the text
+    // direction, where newlines return to column `margin`. Returns the position
+    // after the last character. If `opaque` is true, the cell of each
+    // character is cleared first.
+    fn print_from(&mut self, x : usize, y : usize, margin : usize, s : &str, scale : usize, opaque : bool) -> (usize, usize) {
+        if scale == 0 {
+            return (x, y)
+        }
+        match self.direction {
+            TextDirection::LeftRight => self.print_left_right(x, y, margin, s, scale, opaque),
+            TextDirection::RightLeft => self.print_right_left(x, y, margin, s, scale, opaque),
+            TextDirection::TopBottom => self.print_top_bottom(x, y, s, scale, opaque)
+        }
+    }
+
+    // Same as print_from, from left to right.
+    fn print_left_right(&mut self, x : usize, y : usize, margin : usize, s : &str, scale : usize, opaque : bool) -> (usize, usize) {
+        let (width, height) = self.dimensions();
+        let cell_width  = (self.font.width() + self.char_spacing) * scale;
+        let cell_height = self.font.height() * scale;
+        let tab_width = TAB_STOP * cell_width;
+        let mut xp = x * cell_width;
         let mut yc = y;
         for c in s.chars() {
-            self.print_char(xc, yc, c);
-            xc += 1;
-            if xc * (terminus6x12::WIDTH + self.char_spacing) >= LCDWIDTH {
-                xc = 0;
-                yc += 1;
-                if yc * terminus6x12::HEIGHT >= LCDHEIGHT {
-                    break;
+            match c {
+                '\n' => {
+                    xp = margin * cell_width;
+                    yc += 1;
+                }
+                '\r' => xp = margin * cell_width,
+                '\t' => xp = (xp / tab_width + 1) * tab_width,
+                _    => {
+                    let advance = self.glyph_advance(c) * scale;
+                    // In proportional mode, do not cut glyphs at the right edge.
+                    if self.proportional && xp > 0 && xp + advance - scale > width {
+                        xp = 0;
+                        yc += 1;
+                        if yc * cell_height >= height {
+                            break;
+                        }
+                    }
+                    if opaque {
+                        self.clear_region(xp, yc * cell_height, advance, cell_height);
+                    }
+                    self.draw_char_at(xp, yc * cell_height, c, scale);
+                    xp += advance;
                 }
             }
+            if xp >= width {
+                xp = 0;
+                yc += 1;
+            }
+            if yc * cell_height >= height {
+                break;
+            }
+        }
+        (xp.div_ceil(cell_width), yc)
+    }
+
+    pub fn print_centered(&mut self, y : usize, s : &str) {
+        let (width, _) = self.dimensions();
+        let text_width = self.measure_text(s);
+        let xp = width.saturating_sub(text_width) / 2;
+        self.print_at(xp, y * self.font.height(), s);
+    }
+
+    pub fn print_right(&mut self, y : usize, s : &str) {
+        let (width, _) = self.dimensions();
+        let text_width = self.measure_text(s);
+        let xp = width.saturating_sub(text_width);
+        self.print_at(xp, y * self.font.height(), s);
+    }
+
+    /// The width in pixels of a string printed with the active font,
+    /// including the spacing after each character.
+    ///
+    /// Control characters are interpreted as in `print`: for a multi-line
+    /// string, this is the width of the longest line; `\r` starts the line
+    /// over and `\t` advances to the next tab stop. Other control characters
+    /// count as one character, as they are printed as a replacement glyph.
+    pub fn measure_text(&self, s : &str) -> usize {
+        let tab_width = TAB_STOP * (self.font.width() + self.char_spacing);
+        let mut x = 0;
+        let mut max_x = 0;
+        for c in s.chars() {
+            match c {
+                '\n' | '\r' => x = 0,
+                '\t'        => x = (x / tab_width + 1) * tab_width,
+                _           => x += self.glyph_advance(c)
+            }
+            max_x = max(max_x, x);
+        }
+        max_x
+    }
+
+    // Print a string from the given pixel coordinates, on a single line.
+    // Characters that do not entirely fit in the display are not drawn.
+    fn print_at(&mut self, xp : usize, yp : usize, s : &str) {
+        let (width, _) = self.dimensions();
+        let mut x = xp;
+        for c in s.chars() {
+            let advance = self.glyph_advance(c);
+            if x + min(advance, self.font.width()) > width {
+                break;
+            }
+            self.draw_char_at(x, yp, c, 1);
+            x += advance;
         }
     }
 }
@@ -0,0 +1,386 @@
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use sysfs_gpio::{self, Direction, Pin};
+use spidev::{Spidev, SpidevOptions};
+pub use spidev::{SpiModeFlags, SPI_MODE_0, SPI_MODE_1, SPI_MODE_2, SPI_MODE_3};
+use std::io;
+use std::io::Write;
+use std::convert::Infallible;
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::thread::{self, sleep, JoinHandle};
+use std::time::Duration;
+use crate::{PCD8544, Orientation, Error, Result, Backlight, DefaultDelay, DEFAULT_CONTRAST, DEFAULT_BIAS, BUFFER_LEN};
+
+/// A Linux spidev device, usable as an embedded-hal SPI bus.
+pub struct SpidevBus(pub Spidev);
+
+impl spi::Write<u8> for SpidevBus {
+    type Error = io::Error;
+
+    fn write(&mut self, words : &[u8]) -> io::Result<()> {
+        // io::Write::write may send only a part of the words, which the
+        // embedded-hal trait does not allow.
+        self.0.write_all(words)
+    }
+}
+
+/// A sysfs GPIO pin, usable as an embedded-hal output pin.
+///
+/// The pin is unexported when this value is dropped.
+pub struct SysfsPin(pub Pin);
+
+impl SysfsPin {
+    /// Unexport the pin, reporting errors instead of ignoring them.
+    pub fn unexport(self) -> sysfs_gpio::Result<()> {
+        let pin = self.0;
+        // Skip the unexport in drop().
+        mem::forget(self);
+        pin.unexport()
+    }
+}
+
+impl Drop for SysfsPin {
+    fn drop(&mut self) {
+        // Errors cannot be reported during teardown.
+        let _ = self.0.unexport();
+    }
+}
+
+impl OutputPin for SysfsPin {
+    type Error = sysfs_gpio::Error;
+
+    fn set_low(&mut self) -> sysfs_gpio::Result<()> {
+        self.0.set_value(0)
+    }
+
+    fn set_high(&mut self) -> sysfs_gpio::Result<()> {
+        self.0.set_value(1)
+    }
+}
+
+/// A backlight driven by a sysfs GPIO pin, using software PWM.
+///
+/// A thread toggles the pin with a period of 10 ms. The pin is only
+/// written when its state changes, so levels 0 and 255 cost nothing.
+pub struct SysfsBacklight {
+    level : Arc<AtomicU8>,
+    running : Arc<AtomicBool>,
+    thread : Option<JoinHandle<()>>
+}
+
+const PWM_PERIOD_MS : u64 = 10;
+
+fn soft_pwm(mut pin : SysfsPin, level : &AtomicU8, running : &AtomicBool) {
+    let period = Duration::from_millis(PWM_PERIOD_MS);
+    let mut state = None;
+    let mut set = |pin : &mut SysfsPin, value : bool| {
+        if state != Some(value) {
+            // Errors cannot be reported from the PWM thread.
+            let _ = if value { pin.set_high() } else { pin.set_low() };
+            state = Some(value);
+        }
+    };
+
+    while running.load(Ordering::Relaxed) {
+        let l = level.load(Ordering::Relaxed) as u32;
+        let on = period * l / 255;
+        if l > 0 {
+            set(&mut pin, true);
+            sleep(on);
+        }
+        if l < 255 {
+            set(&mut pin, false);
+            sleep(period - on);
+        }
+    }
+    set(&mut pin, false);
+}
+
+impl SysfsBacklight {
+    /// Start driving a backlight from the given pin, initially off.
+    pub fn new(pin : SysfsPin) -> Self {
+        let level = Arc::new(AtomicU8::new(0));
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let level = level.clone();
+            let running = running.clone();
+            thread::spawn(move || soft_pwm(pin, &level, &running))
+        };
+        Self { level, running, thread : Some(thread) }
+    }
+}
+
+impl Backlight for SysfsBacklight {
+    type Error = Infallible;
+
+    fn set_level(&mut self, level : u8) -> ::std::result::Result<(), Infallible> {
+        self.level.store(level, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Drop for SysfsBacklight {
+    fn drop(&mut self) {
+        // Stop the PWM thread, which releases the pin.
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+pub type LinuxPCD8544 = PCD8544<SpidevBus, SysfsPin, SysfsPin, Option<SysfsBacklight>>;
+
+pub type LinuxError = Error<io::Error, sysfs_gpio::Error>;
+
+impl From<sysfs_gpio::Error> for LinuxError {
+    fn from(e : sysfs_gpio::Error) -> LinuxError {
+        Error::PinError(e)
+    }
+}
+
+impl From<io::Error> for LinuxError {
+    fn from(e : io::Error) -> LinuxError {
+        Error::SpiDevError(e)
+    }
+}
+
+/// The result of the operations of a display connected through spidev and
+/// sysfs GPIO.
+pub type LinuxResult<T> = ::std::result::Result<T, LinuxError>;
+
+fn new_pin(n : u64, dir : Direction, timeout : Duration, retries : u32) -> LinuxResult<SysfsPin> {
+    let pin = Pin::new(n);
+
+    // Assume the pin will be correctly configured.
+    let mut res : LinuxResult<()> = Ok(());
+
+    // Export the sysfs entry for the chosen pin.
+    pin.export()?;
+
+    // The sysfs entry might not be immediately usable
+    // after the export operation.
+    // We will call set_direction() repeatedly until the operation completes
+    // or after a given number of attempts.
+    for k in 0..retries {
+        if k > 0 {
+            sleep(timeout);
+        }
+        match pin.set_direction(dir) {
+            Ok(_)  => return Ok(SysfsPin(pin)),
+            Err(e) => res = Err(Error::from(e))
+        }
+    }
+
+    // Return the last result.
+    res.map(|_| SysfsPin(pin))
+}
+
+/// Configuration of a display connected through spidev and sysfs GPIO.
+pub struct PCD8544Builder {
+    spi_speed_hz : u32,
+    spi_mode : SpiModeFlags,
+    bits_per_word : u8,
+    contrast : u8,
+    bias : u8,
+    orient : Orientation,
+    char_spacing : usize,
+    gpio_retries : u32,
+    gpio_timeout : Duration,
+    backlight : Option<u64>,
+    max_transfer_bytes : usize,
+    spi_write_retries : u32,
+    init_commands : Option<Vec<u8>>,
+    skip_init : bool
+}
+
+impl PCD8544Builder {
+    pub fn new() -> Self {
+        Self {
+            spi_speed_hz : 4_000_000,
+            spi_mode : SPI_MODE_0,
+            bits_per_word : 8,
+            contrast : DEFAULT_CONTRAST,
+            bias : DEFAULT_BIAS,
+            orient : Orientation::Landscape(false),
+            char_spacing : 0,
+            gpio_retries : 3,
+            gpio_timeout : Duration::from_millis(100),
+            backlight : None,
+            max_transfer_bytes : BUFFER_LEN,
+            spi_write_retries : 1,
+            init_commands : None,
+            skip_init : false
+        }
+    }
+
+    pub fn spi_speed_hz(mut self, hz : u32) -> Self {
+        self.spi_speed_hz = hz;
+        self
+    }
+
+    /// The SPI mode, `SPI_MODE_0` by default.
+    ///
+    /// The controller samples data on the rising edge of the clock, as in
+    /// mode 0: `build` returns `Error::InvalidParameter` for other modes.
+    pub fn spi_mode(mut self, mode : SpiModeFlags) -> Self {
+        self.spi_mode = mode;
+        self
+    }
+
+    /// The size of an SPI word, 8 bits by default.
+    ///
+    /// Commands and data are 8-bit bytes: `build` returns
+    /// `Error::InvalidParameter` for other sizes.
+    pub fn bits_per_word(mut self, bits : u8) -> Self {
+        self.bits_per_word = bits;
+        self
+    }
+
+    pub fn contrast(mut self, contrast : u8) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    pub fn bias(mut self, bias : u8) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    pub fn orientation(mut self, orient : Orientation) -> Self {
+        self.orient = orient;
+        self
+    }
+
+    pub fn char_spacing(mut self, spacing : usize) -> Self {
+        self.char_spacing = spacing;
+        self
+    }
+
+    /// The number of attempts to configure each GPIO pin after exporting it.
+    pub fn gpio_retries(mut self, retries : u32) -> Self {
+        self.gpio_retries = retries;
+        self
+    }
+
+    /// The delay between two attempts to configure a GPIO pin.
+    pub fn gpio_timeout(mut self, timeout : Duration) -> Self {
+        self.gpio_timeout = timeout;
+        self
+    }
+
+    /// The GPIO pin connected to the backlight, if any.
+    pub fn backlight(mut self, pin : u64) -> Self {
+        self.backlight = Some(pin);
+        self
+    }
+
+    /// The maximum size of a single SPI write.
+    pub fn max_transfer_bytes(mut self, n : usize) -> Self {
+        self.max_transfer_bytes = n;
+        self
+    }
+
+    /// The number of attempts for each SPI write, 1 by default.
+    pub fn spi_write_retries(mut self, attempts : u32) -> Self {
+        self.spi_write_retries = attempts;
+        self
+    }
+
+    /// Send these commands after the reset, instead of setting the contrast
+    /// and bias.
+    pub fn init_commands(mut self, commands : &[u8]) -> Self {
+        self.init_commands = Some(commands.to_vec());
+        self
+    }
+
+    /// Do not reset the controller nor send any command in `build`, for a
+    /// warm restart where the panel kept power and its configuration.
+    ///
+    /// The reset pin is driven high as soon as it is exported, so that the
+    /// display keeps its content until the buffer is sent with `update`.
+    /// The driver cannot check the state of the controller: if the panel
+    /// lost power or was reconfigured, it stays blank or shows garbage
+    /// until `reset` and the contrast and bias are set again. `contrast`
+    /// and `bias` return the values given to this builder.
+    pub fn skip_init(mut self, skip : bool) -> Self {
+        self.skip_init = skip;
+        self
+    }
+
+    pub fn build(self, dc : u64, rst : u64, spi : &str) -> LinuxResult<LinuxPCD8544> {
+        if self.spi_mode != SPI_MODE_0 || self.bits_per_word != 8 {
+            return Err(Error::InvalidParameter)
+        }
+
+        let mut spidev = Spidev::open(spi)?;
+        let mut options = SpidevOptions::new();
+        options.bits_per_word(self.bits_per_word).max_speed_hz(self.spi_speed_hz).mode(self.spi_mode);
+        spidev.configure(&options)?;
+
+        let dc  = new_pin(dc,  Direction::Out, self.gpio_timeout, self.gpio_retries)?;
+        // Setting the direction to Out drives the pin low, which would reset the controller.
+        let rst_dir = if self.skip_init { Direction::High } else { Direction::Out };
+        let rst = new_pin(rst, rst_dir, self.gpio_timeout, self.gpio_retries)?;
+
+        let backlight = match self.backlight {
+            Some(n) => Some(SysfsBacklight::new(new_pin(n, Direction::Out, self.gpio_timeout, self.gpio_retries)?)),
+            None    => None
+        };
+
+        let mut res = PCD8544::with_hal(SpidevBus(spidev), dc, rst, backlight);
+        self.configure(&mut res, &mut DefaultDelay)?;
+        Ok(res)
+    }
+
+    // Apply the settings of the driver, then initialize the controller
+    // unless `skip_init` was set. The mock displays use this too.
+    pub(crate) fn configure<SPI, DC, RST, BL, D, SpiE, PinE>(&self, res : &mut PCD8544<SPI, DC, RST, BL>, delay : &mut D) -> Result<(), SpiE, PinE>
+        where SPI : spi::Write<u8, Error = SpiE>,
+              DC  : OutputPin<Error = PinE>,
+              RST : OutputPin<Error = PinE>,
+              D   : DelayMs<u16> {
+        res.orient = self.orient;
+        res.char_spacing = self.char_spacing;
+        res.set_max_transfer_bytes(self.max_transfer_bytes);
+        res.set_spi_write_retries(self.spi_write_retries);
+        match self.init_commands {
+            _ if self.skip_init => {
+                res.contrast = self.contrast;
+                res.bias = self.bias;
+            }
+            Some(ref commands) => {
+                res.reset_with_delay(delay)?;
+                res.init_with(commands)?;
+            }
+            None => res.init_with_delay(self.contrast, self.bias, delay)?
+        }
+        Ok(())
+    }
+}
+
+impl Default for PCD8544Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinuxPCD8544 {
+    pub fn new(dc : u64, rst : u64, spi : &str, orient : Orientation) -> LinuxResult<Self> {
+        PCD8544Builder::new().orientation(orient).build(dc, rst, spi)
+    }
+
+    /// Release the GPIO pins used by the display.
+    ///
+    /// Dropping the display also unexports its pins, but ignores errors.
+    pub fn release(self) -> LinuxResult<()> {
+        let PCD8544 { dc, rst, .. } = self;
+        dc.unexport()?;
+        rst.unexport()?;
+        Ok(())
+    }
+}
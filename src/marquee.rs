@@ -0,0 +1,65 @@
+
+use crate::PCD8544;
+
+/// Scroll a line of text continuously from right to left, for text that is
+/// wider than the display.
+///
+/// The text must not contain control characters. Each call to `tick` draws
+/// one step of the animation in the buffer; the caller updates the display.
+pub struct Marquee<'a> {
+    text : &'a str,
+    y : usize,
+    pos : usize,
+    speed : usize,
+    gap : usize
+}
+
+impl<'a> Marquee<'a> {
+    /// A marquee on the band of the display starting at pixel row `y`,
+    /// moving by 1 pixel per tick with a 16-pixel gap between repetitions.
+    pub fn new(text : &'a str, y : usize) -> Self {
+        Self { text, y, pos : 0, speed : 1, gap : 16 }
+    }
+
+    /// The number of pixels the text moves at each tick.
+    pub fn speed(mut self, pixels : usize) -> Self {
+        self.speed = pixels;
+        self
+    }
+
+    /// The number of blank pixels between the end of the text and the
+    /// beginning of the next repetition.
+    pub fn gap(mut self, pixels : usize) -> Self {
+        self.gap = pixels;
+        self
+    }
+
+    /// Clear the band of the marquee and draw the text at the current
+    /// offset, then advance the offset.
+    pub fn tick<SPI, DC, RST, BL>(&mut self, lcd : &mut PCD8544<SPI, DC, RST, BL>) {
+        let (width, _) = lcd.dimensions();
+        lcd.clear_region(0, self.y, width, lcd.font.height());
+        // The distance between two repetitions of the text.
+        let period = lcd.measure_text(self.text) + self.gap;
+        if period == 0 {
+            return
+        }
+
+        let mut start = -(self.pos as isize);
+        while start < width as isize {
+            let mut xp = start;
+            for c in self.text.chars() {
+                let advance = lcd.glyph_advance(c) as isize;
+                if xp >= width as isize {
+                    break;
+                }
+                if xp + advance > 0 {
+                    lcd.draw_char_clipped(xp, self.y as isize, c, 1, width);
+                }
+                xp += advance;
+            }
+            start += period as isize;
+        }
+        self.pos = (self.pos + self.speed) % period;
+    }
+}
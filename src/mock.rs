@@ -0,0 +1,132 @@
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+use crate::{PCD8544, Orientation, Error, NoBacklight, DEFAULT_CONTRAST, DEFAULT_BIAS};
+#[cfg(feature = "linux")]
+use crate::PCD8544Builder;
+#[cfg(feature = "async")]
+use crate::AsyncSpiWrite;
+
+// The bytes received by a mock display, sorted by the state of the DC pin.
+#[derive(Default)]
+struct Log {
+    dc : bool,
+    commands : Vec<u8>,
+    data : Vec<u8>
+}
+
+/// An in-memory SPI bus that records the bytes written to it.
+pub struct MockSpi(Rc<RefCell<Log>>);
+
+impl spi::Write<u8> for MockSpi {
+    type Error = Infallible;
+
+    fn write(&mut self, words : &[u8]) -> Result<(), Infallible> {
+        let mut log = self.0.borrow_mut();
+        if log.dc {
+            log.data.extend_from_slice(words);
+        }
+        else {
+            log.commands.extend_from_slice(words);
+        }
+        Ok(())
+    }
+}
+
+// Mock writes complete immediately.
+#[cfg(feature = "async")]
+impl AsyncSpiWrite for MockSpi {
+    type Error = Infallible;
+
+    async fn write_async(&mut self, words : &[u8]) -> Result<(), Infallible> {
+        spi::Write::write(self, words)
+    }
+}
+
+/// An in-memory output pin.
+///
+/// The DC pin of a mock display tells the SPI bus whether it receives
+/// commands or data.
+pub struct MockPin(Option<Rc<RefCell<Log>>>);
+
+impl OutputPin for MockPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        if let Some(ref log) = self.0 {
+            log.borrow_mut().dc = false;
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        if let Some(ref log) = self.0 {
+            log.borrow_mut().dc = true;
+        }
+        Ok(())
+    }
+}
+
+// A delay that returns immediately, so that the reset does not sleep.
+struct NoDelay;
+
+impl DelayMs<u16> for NoDelay {
+    fn delay_ms(&mut self, _ : u16) {}
+}
+
+pub type MockPCD8544 = PCD8544<MockSpi, MockPin, MockPin>;
+
+/// The result of the operations of a mock display, which never fail.
+pub type MockResult<T> = crate::Result<T, Infallible, Infallible>;
+
+impl MockPCD8544 {
+    /// Create a display that records the bytes it sends instead of
+    /// accessing hardware.
+    pub fn new_mock(orient : Orientation) -> Self {
+        let log = Rc::new(RefCell::new(Log::default()));
+        let spi = MockSpi(log.clone());
+        let dc  = MockPin(Some(log));
+        let rst = MockPin(None);
+        let mut res = PCD8544::with_hal(spi, dc, rst, NoBacklight);
+        match res.init_with_delay(DEFAULT_CONTRAST, DEFAULT_BIAS, &mut NoDelay) {
+            Ok(()) => (),
+            Err(Error::PinError(e)) | Err(Error::SpiDevError(e)) => match e {},
+            // The default contrast and bias are in range, and nothing is
+            // parsed nor read: the init_sequence test checks the commands.
+            Err(Error::InvalidParameter) | Err(Error::Format) | Err(Error::InvalidBufferSize) | Err(Error::Io(_)) => ()
+        }
+        res.orient = orient;
+        res
+    }
+
+    /// Create a mock display configured by a builder, as `build` would
+    /// configure a Linux display. The SPI and GPIO settings are ignored.
+    #[cfg(feature = "linux")]
+    pub fn from_builder(builder : &PCD8544Builder) -> MockResult<Self> {
+        let log = Rc::new(RefCell::new(Log::default()));
+        let mut res = PCD8544::with_hal(MockSpi(log.clone()), MockPin(Some(log)), MockPin(None), NoBacklight);
+        builder.configure(&mut res, &mut NoDelay)?;
+        Ok(res)
+    }
+
+    /// The command bytes sent since the creation of the display.
+    pub fn sent_commands(&self) -> Vec<u8> {
+        self.spi.0.borrow().commands.clone()
+    }
+
+    /// The data bytes sent since the creation of the display.
+    pub fn sent_data(&self) -> Vec<u8> {
+        self.spi.0.borrow().data.clone()
+    }
+
+    /// Forget the bytes sent so far.
+    pub fn clear_sent(&mut self) {
+        let mut log = self.spi.0.borrow_mut();
+        log.commands.clear();
+        log.data.clear();
+    }
+}
@@ -0,0 +1,77 @@
+
+use core::cmp::min;
+use crate::{PCD8544, LCDWIDTH, LCDHEIGHT, ROWPIXELS, BUFFER_LEN};
+
+/// Patterns covering the whole panel, to check the wiring and contrast of a display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestPattern {
+    /// Squares of 4x4 pixels.
+    Checkerboard,
+    /// One column on, one column off.
+    VerticalStripes,
+    /// One row on, one row off.
+    HorizontalStripes,
+    /// A one-pixel frame along the edges of the panel.
+    Border,
+    /// A dithered gradient from white on the left to black on the right.
+    Gradient
+}
+
+// The 4x4 Bayer matrix of ordered dithering, also used by draw_gray.
+pub(crate) const BAYER4 : [[u8 ; 4] ; 4] = [
+    [ 0,  8,  2, 10],
+    [12,  4, 14,  6],
+    [ 3, 11,  1,  9],
+    [15,  7, 13,  5]
+];
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Fill the buffer with a test pattern, in native landscape coordinates.
+    ///
+    /// The pattern ignores the orientation and `inverse`, so that the edges
+    /// of the panel can be checked. Call `update` to show it.
+    pub fn fill_test_pattern(&mut self, pattern : TestPattern) {
+        for (i, b) in self.buffer.iter_mut().enumerate() {
+            let x = i % LCDWIDTH;
+            let row = i / LCDWIDTH;
+            *b = match pattern {
+                TestPattern::Checkerboard      => if (x / 4) & 1 == 0 { 0x0F } else { 0xF0 },
+                TestPattern::VerticalStripes   => if x & 1 == 0 { 0xFF } else { 0x00 },
+                TestPattern::HorizontalStripes => 0x55,
+                TestPattern::Border => {
+                    let mut v = 0x00;
+                    if x == 0 || x == LCDWIDTH - 1 {
+                        v = 0xFF;
+                    }
+                    if row == 0 {
+                        v |= 0x01;
+                    }
+                    if row == LCDHEIGHT / ROWPIXELS - 1 {
+                        v |= 0x80;
+                    }
+                    v
+                }
+                TestPattern::Gradient => {
+                    let level = x * 256 / LCDWIDTH;
+                    (0..ROWPIXELS).filter(|&k| (BAYER4[(row * ROWPIXELS + k) % 4][x % 4] as usize) * 16 + 8 < level)
+                                  .fold(0x00, |v, k| v | (1 << k))
+                }
+            };
+        }
+        self.dirty = Some((0, BUFFER_LEN - 1));
+    }
+
+    /// Fill a rectangle with an 8x8 tile, one byte per row with the most
+    /// significant bit on the left.
+    ///
+    /// The tile is aligned on the origin of the display, so that adjacent
+    /// rectangles join seamlessly. Pixels clear in the tile are turned off.
+    pub fn fill_rect_pattern(&mut self, x : usize, y : usize, w : usize, h : usize, pattern : &[u8 ; 8]) {
+        let (width, height) = self.dimensions();
+        for py in y..min(y.saturating_add(h), height) {
+            for px in x..min(x.saturating_add(w), width) {
+                self.set_pixel(px, py, pattern[py % 8] & (0x80 >> (px % 8)) != 0x00);
+            }
+        }
+    }
+}
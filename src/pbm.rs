@@ -0,0 +1,89 @@
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use crate::{PCD8544, Error, Result};
+
+// Skip whitespace and comments, and return the position of the next token.
+fn skip_space(data : &[u8], mut pos : usize) -> usize {
+    while pos < data.len() {
+        match data[pos] {
+            b'#' => {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            c if c.is_ascii_whitespace() => pos += 1,
+            _ => break
+        }
+    }
+    pos
+}
+
+// Parse a decimal number, and return it with the position after it.
+fn parse_number(data : &[u8], pos : usize) -> Option<(usize, usize)> {
+    let start = skip_space(data, pos);
+    let mut end = start;
+    let mut value : usize = 0;
+    while end < data.len() && data[end].is_ascii_digit() {
+        value = value.checked_mul(10)?.checked_add((data[end] - b'0') as usize)?;
+        end += 1;
+    }
+    if end == start {
+        None
+    }
+    else {
+        Some((value, end))
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Draw an image in Netpbm bitmap format, either ASCII (P1) or binary (P4).
+    ///
+    /// Returns `Error::Format` if the data is not a valid bitmap.
+    pub fn draw_pbm(&mut self, x : usize, y : usize, data : &[u8]) -> Result<(), SpiE, PinE> {
+        if data.len() < 2 || data[0] != b'P' {
+            return Err(Error::Format)
+        }
+        let binary = match data[1] {
+            b'1' => false,
+            b'4' => true,
+            _    => return Err(Error::Format)
+        };
+        let (width,  pos) = parse_number(data, 2).ok_or(Error::Format)?;
+        let (height, pos) = parse_number(data, pos).ok_or(Error::Format)?;
+
+        if binary {
+            // A single whitespace character separates the header from the pixels.
+            match data.get(pos) {
+                Some(c) if c.is_ascii_whitespace() => {}
+                _ => return Err(Error::Format)
+            }
+            let start = pos + 1;
+            let len = width.div_ceil(8).checked_mul(height).ok_or(Error::Format)?;
+            if data.len() < start || data.len() - start < len {
+                return Err(Error::Format)
+            }
+            self.draw_bitmap(x, y, width, height, &data[start .. start + len], false);
+        }
+        else {
+            let mut pos = pos;
+            for r in 0..height {
+                for k in 0..width {
+                    pos = skip_space(data, pos);
+                    let value = match data.get(pos) {
+                        Some(&b'0') => false,
+                        Some(&b'1') => true,
+                        _           => return Err(Error::Format)
+                    };
+                    self.set_pixel(x + k, y + r, value);
+                    pos += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,762 @@
+
+use core::cmp::{min, max};
+use crate::{PCD8544, Orientation, LCDWIDTH, LCDHEIGHT, ROWPIXELS, BUFFER_LEN};
+use crate::trig;
+
+// The largest side of a rounded rectangle or radius of a circle, to bound
+// the drawing loops.
+const MAX_EXTENT : usize = 1 << 15;
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Draw a line between two points, with the Bresenham algorithm.
+    ///
+    /// The line is clipped to the display before drawing, so that only its
+    /// visible part is walked, however far the ends are.
+    pub fn draw_line(&mut self, x0 : usize, y0 : usize, x1 : usize, y1 : usize, value : bool) {
+        self.line_signed(signed(x0), signed(y0), signed(x1), signed(y1), value);
+    }
+
+    /// Draw a horizontal line of `len` pixels, starting at `(x, y)`.
+    ///
+    /// In `Landscape(false)` orientation, this writes the buffer directly
+    /// and is much faster than `draw_line`.
+    pub fn hline(&mut self, x : usize, y : usize, len : usize, value : bool) {
+        if let Orientation::Landscape(false) = self.orient {
+            if len == 0 || x >= LCDWIDTH || y >= LCDHEIGHT {
+                return
+            }
+            let start = x + (y / ROWPIXELS) * LCDWIDTH;
+            let end = start + min(len, LCDWIDTH - x);
+            let bit = 1 << (y % ROWPIXELS);
+            self.write_mask(start, end, bit, value);
+        }
+        else {
+            for k in 0..min(len, LCDWIDTH) {
+                self.set_pixel(x.saturating_add(k), y, value);
+            }
+        }
+    }
+
+    /// Draw a vertical line of `len` pixels, starting at `(x, y)`.
+    ///
+    /// In `Landscape(false)` orientation, this writes whole bytes of the buffer
+    /// at once and is much faster than `draw_line`.
+    pub fn vline(&mut self, x : usize, y : usize, len : usize, value : bool) {
+        if let Orientation::Landscape(false) = self.orient {
+            if len == 0 || x >= LCDWIDTH || y >= LCDHEIGHT {
+                return
+            }
+            let y1 = min(y.saturating_add(len), LCDHEIGHT);
+            for r in y / ROWPIXELS .. y1.div_ceil(ROWPIXELS) {
+                // The bits of this byte-row between y and y1.
+                let lo = max(y, r * ROWPIXELS) - r * ROWPIXELS;
+                let hi = min(y1, (r + 1) * ROWPIXELS) - r * ROWPIXELS;
+                let mask = ((1u16 << hi) - (1u16 << lo)) as u8;
+                let index = x + r * LCDWIDTH;
+                self.write_mask(index, index + 1, mask, value);
+            }
+        }
+        else {
+            for k in 0..min(len, LCDWIDTH) {
+                self.set_pixel(x, y.saturating_add(k), value);
+            }
+        }
+    }
+
+    // Set or clear the bits of `mask` in the buffer bytes from `start` to `end`.
+    fn write_mask(&mut self, start : usize, end : usize, mask : u8, value : bool) {
+        for index in start..end {
+            if value != self.inverse {
+                self.buffer[index] |= mask;
+            }
+            else {
+                self.buffer[index] &= !mask;
+            }
+        }
+        self.mark_dirty(start);
+        self.mark_dirty(end - 1);
+    }
+
+    pub fn draw_rect(&mut self, x : usize, y : usize, w : usize, h : usize, value : bool) {
+        if w == 0 || h == 0 {
+            return
+        }
+        // Clamp far edges to a coordinate that is off-screen in any orientation.
+        let x1 = min(x.saturating_add(w - 1), LCDWIDTH);
+        let y1 = min(y.saturating_add(h - 1), LCDWIDTH);
+        self.draw_line(x,  y,  x1, y,  value);
+        self.draw_line(x,  y1, x1, y1, value);
+        self.draw_line(x,  y,  x,  y1, value);
+        self.draw_line(x1, y,  x1, y1, value);
+    }
+
+    pub fn fill_rect(&mut self, x : usize, y : usize, w : usize, h : usize, value : bool) {
+        // No logical dimension exceeds LCDWIDTH, whatever the orientation.
+        let x1 = min(x.saturating_add(w), LCDWIDTH);
+        let y1 = min(y.saturating_add(h), LCDWIDTH);
+        for py in y..y1 {
+            for px in x..x1 {
+                self.set_pixel(px, py, value);
+            }
+        }
+    }
+
+    /// Set all the pixels of a rectangle to the given value, clipped to the display.
+    pub fn fill_region(&mut self, x : usize, y : usize, w : usize, h : usize, value : bool) {
+        let (width, height) = self.dimensions();
+        if x >= width || y >= height {
+            return
+        }
+        let w = min(w, width - x);
+        for py in y..min(y.saturating_add(h), height) {
+            self.hline(x, py, w, value);
+        }
+    }
+
+    /// Turn off all the pixels of a rectangle, clipped to the display.
+    pub fn clear_region(&mut self, x : usize, y : usize, w : usize, h : usize) {
+        self.fill_region(x, y, w, h, false);
+    }
+
+    /// Invert all the pixels of a rectangle, clipped to the display.
+    pub fn invert_region(&mut self, x : usize, y : usize, w : usize, h : usize) {
+        let (width, height) = self.dimensions();
+        if w == 0 || h == 0 || x >= width || y >= height {
+            return
+        }
+        let x1 = min(x.saturating_add(w), width) - 1;
+        let y1 = min(y.saturating_add(h), height) - 1;
+
+        // Any orientation maps the rectangle to a rectangle in native coordinates.
+        let native = |loc : Option<(usize, u8)>| {
+            let (index, bit) = loc.unwrap();
+            (index % LCDWIDTH, (index / LCDWIDTH) * ROWPIXELS + bit.trailing_zeros() as usize)
+        };
+        let (ax, ay) = native(self.pixel_location(x,  y));
+        let (bx, by) = native(self.pixel_location(x1, y1));
+        let (nx0, nx1) = (min(ax, bx), max(ax, bx));
+        let (ny0, ny1) = (min(ay, by), max(ay, by) + 1);
+
+        for r in ny0 / ROWPIXELS .. ny1.div_ceil(ROWPIXELS) {
+            let lo = max(ny0, r * ROWPIXELS) - r * ROWPIXELS;
+            let hi = min(ny1, (r + 1) * ROWPIXELS) - r * ROWPIXELS;
+            let mask = ((1u16 << hi) - (1u16 << lo)) as u8;
+            for b in self.buffer[r * LCDWIDTH + nx0 ..= r * LCDWIDTH + nx1].iter_mut() {
+                *b ^= mask;
+            }
+        }
+        self.mark_dirty(nx0 + (ny0 / ROWPIXELS) * LCDWIDTH);
+        self.mark_dirty(nx1 + ((ny1 - 1) / ROWPIXELS) * LCDWIDTH);
+    }
+
+    /// Draw the outline of a circle.
+    ///
+    /// A radius of 0 draws the center only. Circles with a radius larger
+    /// than 32768 pixels are not drawn.
+    pub fn draw_circle(&mut self, cx : usize, cy : usize, radius : usize, value : bool) {
+        if !circle_visible(cx, cy, radius) {
+            return
+        }
+        // Midpoint circle algorithm, drawing the eight octants at once.
+        let (cx, cy) = (cx as isize, cy as isize);
+        let mut x = radius as isize;
+        let mut y = 0;
+        let mut err = 1 - x;
+
+        while x >= y {
+            self.set_pixel_signed(cx + x, cy + y, value);
+            self.set_pixel_signed(cx + y, cy + x, value);
+            self.set_pixel_signed(cx - y, cy + x, value);
+            self.set_pixel_signed(cx - x, cy + y, value);
+            self.set_pixel_signed(cx - x, cy - y, value);
+            self.set_pixel_signed(cx - y, cy - x, value);
+            self.set_pixel_signed(cx + y, cy - x, value);
+            self.set_pixel_signed(cx + x, cy - y, value);
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            }
+            else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Fill a circle, including its outline.
+    ///
+    /// A radius of 0 draws the center only. Circles with a radius larger
+    /// than 32768 pixels are not drawn.
+    pub fn fill_circle(&mut self, cx : usize, cy : usize, radius : usize, value : bool) {
+        if !circle_visible(cx, cy, radius) {
+            return
+        }
+        // Same as draw_circle, but join symmetric points with horizontal spans.
+        let (cx, cy) = (cx as isize, cy as isize);
+        let mut x = radius as isize;
+        let mut y = 0;
+        let mut err = 1 - x;
+
+        while x >= y {
+            self.hspan_signed(cx - x, cx + x, cy + y, value);
+            self.hspan_signed(cx - x, cx + x, cy - y, value);
+            self.hspan_signed(cx - y, cx + y, cy + x, value);
+            self.hspan_signed(cx - y, cx + y, cy - x, value);
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            }
+            else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_triangle(&mut self, x0 : usize, y0 : usize, x1 : usize, y1 : usize, x2 : usize, y2 : usize, value : bool) {
+        self.draw_line(x0, y0, x1, y1, value);
+        self.draw_line(x1, y1, x2, y2, value);
+        self.draw_line(x2, y2, x0, y0, value);
+    }
+
+    /// Fill a triangle, including its outline.
+    ///
+    /// Only the rows of the display are scanned, whatever the coordinates of
+    /// the vertices.
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_triangle(&mut self, x0 : usize, y0 : usize, x1 : usize, y1 : usize, x2 : usize, y2 : usize, value : bool) {
+        // Sort the vertices by increasing y. The products of the edge
+        // equations are computed in 128 bits, with a row difference that
+        // is at most LCDWIDTH.
+        let mut v = [(x0 as i128, y0 as i128), (x1 as i128, y1 as i128), (x2 as i128, y2 as i128)];
+        v.sort_unstable_by_key(|&(_, y)| y);
+        let [(x0, y0), (x1, y1), (x2, y2)] = v;
+        let end = LCDWIDTH as i128;
+        let span = |lcd : &mut Self, a : i128, b : i128, y : i128| {
+            // Clamp the span to the display before converting it.
+            let clamp = |x : i128| max(-1, min(x, end)) as isize;
+            lcd.hspan_signed(clamp(min(a, b)), clamp(max(a, b)), y as isize, value);
+        };
+
+        if y0 == y2 {
+            // All vertices on the same row.
+            if y0 <= end {
+                span(self, min(x0, min(x1, x2)), max(x0, max(x1, x2)), y0);
+            }
+            return
+        }
+
+        // Walk the long edge (0-2) and the two short edges (0-1, 1-2),
+        // filling the span between them on each row.
+        for y in y0 ..= min(y2, end) {
+            let xa = x0 + (x2 - x0) * (y - y0) / (y2 - y0);
+            let xb = if y < y1 {
+                x0 + (x1 - x0) * (y - y0) / (y1 - y0)
+            }
+            else if y2 > y1 {
+                x1 + (x2 - x1) * (y - y1) / (y2 - y1)
+            }
+            else {
+                x1
+            };
+            span(self, xa, xb, y);
+        }
+    }
+
+    /// Draw the outline of an ellipse with horizontal and vertical radii.
+    ///
+    /// Ellipses with a radius larger than 32768 pixels are not drawn.
+    pub fn draw_ellipse(&mut self, cx : usize, cy : usize, rx : usize, ry : usize, value : bool) {
+        if !ellipse_visible(cx, cy, rx, ry) {
+            return
+        }
+        let (cx, cy) = (cx as isize, cy as isize);
+        self.ellipse_quadrant(rx, ry, |lcd, x, y| {
+            lcd.set_pixel_signed(cx + x, cy + y, value);
+            lcd.set_pixel_signed(cx - x, cy + y, value);
+            lcd.set_pixel_signed(cx - x, cy - y, value);
+            lcd.set_pixel_signed(cx + x, cy - y, value);
+        });
+    }
+
+    /// Fill an ellipse, including its outline.
+    ///
+    /// Ellipses with a radius larger than 32768 pixels are not drawn.
+    pub fn fill_ellipse(&mut self, cx : usize, cy : usize, rx : usize, ry : usize, value : bool) {
+        if !ellipse_visible(cx, cy, rx, ry) {
+            return
+        }
+        let (cx, cy) = (cx as isize, cy as isize);
+        self.ellipse_quadrant(rx, ry, |lcd, x, y| {
+            lcd.hspan_signed(cx - x, cx + x, cy + y, value);
+            lcd.hspan_signed(cx - x, cx + x, cy - y, value);
+        });
+    }
+
+    // Call `plot` with the points of the quadrant of an ellipse centered on
+    // the origin, with x and y positive.
+    fn ellipse_quadrant<F>(&mut self, rx : usize, ry : usize, mut plot : F)
+        where F : FnMut(&mut Self, isize, isize) {
+        let (rx, ry) = (rx as isize, ry as isize);
+        // Flat ellipses are lines.
+        if ry == 0 {
+            for x in 0 ..= rx {
+                plot(self, x, 0);
+            }
+            return
+        }
+        if rx == 0 {
+            for y in 0 ..= ry {
+                plot(self, 0, y);
+            }
+            return
+        }
+
+        // Midpoint ellipse algorithm, in two regions where the slope of the
+        // curve is above and below 1. The decision terms are computed in 64
+        // bits, which holds rx^2 * ry^2 for radii up to 32768.
+        let (rx2, ry2) = (rx as i64 * rx as i64, ry as i64 * ry as i64);
+        let mut x = 0;
+        let mut y = ry;
+        let mut px = 0;
+        let mut py = 2 * rx2 * y as i64;
+
+        let mut p = ry2 - rx2 * ry as i64 + rx2 / 4;
+        while px < py {
+            plot(self, x, y);
+            x += 1;
+            px += 2 * ry2;
+            if p < 0 {
+                p += ry2 + px;
+            }
+            else {
+                y -= 1;
+                py -= 2 * rx2;
+                p += ry2 + px - py;
+            }
+        }
+
+        let (xl, yl) = (x as i64, y as i64 - 1);
+        p = ry2 * (xl * xl + xl) + rx2 * yl * yl - rx2 * ry2;
+        while y >= 0 {
+            plot(self, x, y);
+            y -= 1;
+            py -= 2 * rx2;
+            if p > 0 {
+                p += rx2 - py;
+            }
+            else {
+                x += 1;
+                px += 2 * ry2;
+                p += rx2 - py + px;
+            }
+        }
+    }
+
+    /// Draw a rectangle with corners rounded to quarter circles.
+    ///
+    /// The radius is clamped to fit in the smaller side of the rectangle.
+    /// The rectangle can extend past the display, up to 32768 pixels on
+    /// each side.
+    pub fn draw_round_rect(&mut self, x : usize, y : usize, w : usize, h : usize, radius : usize, value : bool) {
+        // No logical coordinate reaches LCDWIDTH, whatever the orientation.
+        if w == 0 || h == 0 || x >= LCDWIDTH || y >= LCDWIDTH {
+            return
+        }
+        let (w, h) = (min(w, MAX_EXTENT), min(h, MAX_EXTENT));
+        let r = min(radius, (min(w, h) - 1) / 2);
+        if r == 0 {
+            return self.draw_rect(x, y, w, h, value)
+        }
+
+        // Edges and centers of the corners.
+        let (x0, y0) = (x as isize, y as isize);
+        let (x1, y1) = (x0 + w as isize - 1, y0 + h as isize - 1);
+        let r = r as isize;
+        let (l, t) = (x0 + r, y0 + r);
+        let (rt, b) = (x1 - r, y1 - r);
+
+        // Shorten the edges to the display, so that they are not walked
+        // pixel by pixel far outside of it.
+        let end = LCDWIDTH as isize;
+        self.line_signed(l, y0, min(rt, end), y0, value);
+        self.line_signed(l, y1, min(rt, end), y1, value);
+        self.line_signed(x0, t, x0, min(b, end), value);
+        self.line_signed(x1, t, x1, min(b, end), value);
+
+        // Midpoint circle algorithm, one quadrant per corner.
+        let mut px = r;
+        let mut py = 0;
+        let mut err = 1 - px;
+        while px >= py {
+            for &(dx, dy) in &[(px, py), (py, px)] {
+                self.set_pixel_signed(rt + dx, t - dy, value);
+                self.set_pixel_signed(l - dx,  t - dy, value);
+                self.set_pixel_signed(l - dx,  b + dy, value);
+                self.set_pixel_signed(rt + dx, b + dy, value);
+            }
+            py += 1;
+            if err < 0 {
+                err += 2 * py + 1;
+            }
+            else {
+                px -= 1;
+                err += 2 * (py - px) + 1;
+            }
+        }
+    }
+
+    /// Draw an arc of a circle, from `start_deg` to `end_deg` counterclockwise.
+    ///
+    /// Angles are in degrees, with 0 pointing right and 90 pointing up.
+    /// A span of 360 degrees or more draws the full circle.
+    pub fn draw_arc(&mut self, cx : usize, cy : usize, radius : usize, start_deg : i32, end_deg : i32, value : bool) {
+        let span = end_deg as i64 - start_deg as i64;
+        let span = if span >= 360 { 360 } else { span.rem_euclid(360) as i32 };
+        let start = start_deg.rem_euclid(360);
+        // The points are computed in 128 bits, and saturate to the range of
+        // isize: the lines between them are clipped to the display.
+        let (cx, cy, r) = (cx as i128, cy as i128, radius as i128);
+        let saturate = |v : i128| max(isize::MIN as i128, min(v, isize::MAX as i128)) as isize;
+        let point = |deg : i32| {
+            (saturate(cx + r * trig::cos(deg) as i128 / trig::ONE as i128),
+             saturate(cy - r * trig::sin(deg) as i128 / trig::ONE as i128))
+        };
+
+        // Join the points at each degree, to leave no gaps on large circles.
+        let (mut x0, mut y0) = point(start);
+        self.set_pixel_signed(x0, y0, value);
+        for d in 1..=span {
+            let (x1, y1) = point(start + d);
+            self.line_signed(x0, y0, x1, y1, value);
+            x0 = x1;
+            y0 = y1;
+        }
+    }
+
+    // Draw a line from signed coordinates, ignoring pixels at negative positions.
+    pub(crate) fn line_signed(&mut self, x0 : isize, y0 : isize, x1 : isize, y1 : isize, value : bool) {
+        self.line_dashed(x0, y0, x1, y1, 1, 0, value);
+    }
+
+    // Draw a line from signed coordinates, where the pixels along the path
+    // repeat `on` drawn pixels followed by `off` skipped pixels.
+    #[allow(clippy::too_many_arguments)]
+    fn line_dashed(&mut self, x0 : isize, y0 : isize, x1 : isize, y1 : isize, on : usize, off : usize, value : bool) {
+        let period = (on + off) as u64;
+        self.walk_line(x0, y0, x1, y1, 0, |lcd, step, x, y| {
+            if off == 0 || step % period < on as u64 {
+                lcd.set_pixel_signed(x, y, value);
+            }
+        });
+    }
+
+    // Call `f` with the step number and the coordinates of each point of
+    // the path from (x0, y0) to (x1, y1), except those more than `margin`
+    // pixels away from the display.
+    fn walk_line<F>(&mut self, x0 : isize, y0 : isize, x1 : isize, y1 : isize, margin : isize, mut f : F)
+        where F : FnMut(&mut Self, u64, isize, isize) {
+        let Some((x0, y0, x1, y1, mut step)) = clip_line(x0, y0, x1, y1, margin) else {
+            return
+        };
+
+        // Integer Bresenham algorithm, valid for all octants.
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let mut x = x0;
+        let mut y = y0;
+
+        loop {
+            f(self, step, x, y);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+            step += 1;
+        }
+    }
+
+    /// Draw a line `thickness` pixels wide, centered on the path of
+    /// `draw_line`, with square caps.
+    ///
+    /// The line is made of spans across its main direction, lengthened so
+    /// that the width is measured perpendicularly to the line. A thickness of
+    /// 1 is the same as `draw_line`, and 0 draws nothing. The thickness is
+    /// limited to 32768 pixels.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_thick_line(&mut self, x0 : usize, y0 : usize, x1 : usize, y1 : usize, thickness : usize, value : bool) {
+        if thickness <= 1 {
+            if thickness == 1 {
+                self.draw_line(x0, y0, x1, y1, value);
+            }
+            return
+        }
+        let thickness = min(thickness, MAX_EXTENT) as u64;
+        let (x0, y0, x1, y1) = (signed(x0), signed(y0), signed(x1), signed(y1));
+        let dx = (x1 as i128 - x0 as i128).unsigned_abs();
+        let dy = (y1 as i128 - y0 as i128).unsigned_abs();
+        // Scale the direction down, so that its length cannot overflow.
+        let shift = (128 - max(dx, dy).leading_zeros()).saturating_sub(31);
+        let (dx, dy) = ((dx >> shift) as u64, (dy >> shift) as u64);
+        let major = max(dx, dy);
+        // A point has the thickness in every direction.
+        let span = (thickness * isqrt(dx * dx + dy * dy) + major / 2).checked_div(major)
+            .unwrap_or(thickness) as isize;
+
+        let before = (span - 1) / 2;
+        let after = span - 1 - before;
+        let end = LCDWIDTH as isize;
+        self.walk_line(x0, y0, x1, y1, span, |lcd, _, x, y| {
+            if dx >= dy {
+                for py in max(y - before, 0) ..= min(y + after, end) {
+                    lcd.set_pixel_signed(x, py, value);
+                }
+            }
+            else {
+                lcd.hspan_signed(x - before, x + after, y, value);
+            }
+        });
+    }
+
+    /// Draw a dashed line, repeating `on` pixels drawn and `off` pixels
+    /// left unchanged along the path of `draw_line`, from `(x0, y0)`.
+    ///
+    /// With `off` equal to 0, this is the same as `draw_line`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_dashed_line(&mut self, x0 : usize, y0 : usize, x1 : usize, y1 : usize, on : usize, off : usize, value : bool) {
+        self.line_dashed(signed(x0), signed(y0), signed(x1), signed(y1), on, off, value);
+    }
+
+    /// Join consecutive points with lines, and the last point to the first
+    /// one if `closed` is true.
+    pub fn draw_polyline(&mut self, points : &[(usize, usize)], closed : bool, value : bool) {
+        for w in points.windows(2) {
+            self.draw_line(w[0].0, w[0].1, w[1].0, w[1].1, value);
+        }
+        if closed && points.len() > 2 {
+            let (first, last) = (points[0], points[points.len() - 1]);
+            self.draw_line(last.0, last.1, first.0, first.1, value);
+        }
+    }
+
+    /// Fill a polygon, including its outline, with the even-odd rule.
+    pub fn fill_polygon(&mut self, points : &[(usize, usize)], value : bool) {
+        if points.is_empty() {
+            return
+        }
+        let (width, height) = self.dimensions();
+        let x_min = points.iter().map(|p| p.0).min().unwrap();
+        let x_max = min(points.iter().map(|p| p.0).max().unwrap(), width - 1);
+        let y_min = points.iter().map(|p| p.1).min().unwrap();
+        let y_max = min(points.iter().map(|p| p.1).max().unwrap(), height - 1);
+
+        // Test the center of each pixel against each edge, in coordinates
+        // multiplied by two to keep integers. Coordinates are limited to
+        // 2^60, so that the products of the edge equations fit in 128 bits.
+        let coord = |v : usize| 2 * min(v as u64, 1 << 60) as i128;
+        for y in y_min ..= y_max {
+            let cy = coord(y) + 1;
+            for x in x_min ..= x_max {
+                let cx = coord(x) + 1;
+                let mut inside = false;
+                for (k, &(x1, y1)) in points.iter().enumerate() {
+                    let (x0, y0) = points[if k == 0 { points.len() - 1 } else { k - 1 }];
+                    let (x0, y0, x1, y1) = (coord(x0), coord(y0), coord(x1), coord(y1));
+                    if (y0 > cy) != (y1 > cy) {
+                        // The center is left of the intersection of the edge
+                        // with the horizontal line through it.
+                        let lhs = (cx - x0) * (y1 - y0);
+                        let rhs = (cy - y0) * (x1 - x0);
+                        if (y1 > y0 && lhs < rhs) || (y1 < y0 && lhs > rhs) {
+                            inside = !inside;
+                        }
+                    }
+                }
+                if inside {
+                    self.set_pixel(x, y, value);
+                }
+            }
+        }
+        self.draw_polyline(points, true, value);
+    }
+
+    /// Fill the area of pixels with the same value as `(x, y)`, connected
+    /// horizontally and vertically, with `value`.
+    ///
+    /// The pixels that remain to be processed are kept in a bitmap of one bit
+    /// per pixel, instead of a stack whose size depends on the shape: the fill
+    /// always uses 504 bytes of stack, and never allocates.
+    pub fn flood_fill(&mut self, x : usize, y : usize, value : bool) {
+        let (width, height) = self.dimensions();
+        if x >= width || y >= height {
+            return
+        }
+        let target = self.get_pixel(x, y);
+        if target == value {
+            return
+        }
+
+        let mut pending = [0u8 ; BUFFER_LEN];
+        let mark = |pending : &mut [u8 ; BUFFER_LEN], px : usize, py : usize| {
+            let n = py * width + px;
+            pending[n / 8] |= 1 << (n % 8);
+        };
+        mark(&mut pending, x, y);
+
+        // Resume the search for pending pixels where the last one was found.
+        let mut cursor = 0;
+        while let Some(i) = (0..BUFFER_LEN).map(|k| (cursor + k) % BUFFER_LEN).find(|&k| pending[k] != 0) {
+            cursor = i;
+            let bit = pending[i].trailing_zeros() as usize;
+            pending[i] &= !(1 << bit);
+            let (px, py) = ((i * 8 + bit) % width, (i * 8 + bit) / width);
+            if self.get_pixel(px, py) != target {
+                continue;
+            }
+
+            // Fill the whole span around this pixel, and mark the pixels
+            // above and below it.
+            let mut x0 = px;
+            while x0 > 0 && self.get_pixel(x0 - 1, py) == target {
+                x0 -= 1;
+            }
+            let mut x1 = px;
+            while x1 + 1 < width && self.get_pixel(x1 + 1, py) == target {
+                x1 += 1;
+            }
+            for sx in x0 ..= x1 {
+                self.set_pixel(sx, py, value);
+                if py > 0 && self.get_pixel(sx, py - 1) == target {
+                    mark(&mut pending, sx, py - 1);
+                }
+                if py + 1 < height && self.get_pixel(sx, py + 1) == target {
+                    mark(&mut pending, sx, py + 1);
+                }
+            }
+        }
+    }
+
+    // Set a pixel from signed coordinates, ignoring negative values.
+    fn set_pixel_signed(&mut self, x : isize, y : isize, value : bool) {
+        if x >= 0 && y >= 0 {
+            self.set_pixel(x as usize, y as usize, value);
+        }
+    }
+
+    // Draw a horizontal span from signed coordinates, clipped to the left edge.
+    fn hspan_signed(&mut self, x0 : isize, x1 : isize, y : isize, value : bool) {
+        if y < 0 || x1 < 0 {
+            return
+        }
+        let x0 = if x0 < 0 { 0 } else { x0 as usize };
+        let x1 = min(x1 as usize, LCDWIDTH);
+        if x0 <= x1 {
+            self.draw_line(x0, y as usize, x1, y as usize, value);
+        }
+    }
+}
+
+// Whether a circle has a radius small enough to be drawn and can reach the
+// display. This bounds the loops and the coordinates of the points.
+fn circle_visible(cx : usize, cy : usize, radius : usize) -> bool {
+    radius <= MAX_EXTENT && cx <= LCDWIDTH + radius && cy <= LCDWIDTH + radius
+}
+
+// Same as circle_visible, for an ellipse.
+fn ellipse_visible(cx : usize, cy : usize, rx : usize, ry : usize) -> bool {
+    rx <= MAX_EXTENT && ry <= MAX_EXTENT && cx <= LCDWIDTH + rx && cy <= LCDWIDTH + ry
+}
+
+// Convert a coordinate to a signed one, saturating at isize::MAX.
+fn signed(v : usize) -> isize {
+    min(v, isize::MAX as usize) as isize
+}
+
+// Clip the path from (x0, y0) to (x1, y1) to the square from -margin to
+// LCDWIDTH + margin, which contains the display in any orientation.
+// Returns the ends of the clipped path and the number of steps of the path
+// before it, or None when the path does not cross the square.
+fn clip_line(x0 : isize, y0 : isize, x1 : isize, y1 : isize, margin : isize) -> Option<(isize, isize, isize, isize, u64)> {
+    let (lo, hi) = (-(margin as i128), LCDWIDTH as i128 + margin as i128);
+    let inside = |v : isize| (lo ..= hi).contains(&(v as i128));
+    if inside(x0) && inside(y0) && inside(x1) && inside(y1) {
+        return Some((x0, y0, x1, y1, 0))
+    }
+
+    // Number the points of the path by their steps along the main axis,
+    // from 0 to n, and keep the steps whose point is in the square.
+    let (ax, ay) = (x0 as i128, y0 as i128);
+    let (dx, dy) = (x1 as i128 - ax, y1 as i128 - ay);
+    let n = max(dx.abs(), dy.abs());
+    let (mut first, mut last) = (0, n);
+    for &(a, d) in &[(ax, dx), (ay, dy)] {
+        if d == 0 {
+            if a < lo || a > hi {
+                return None
+            }
+            continue;
+        }
+        // The steps m where a + m * d / n is between lo and hi.
+        let (s0, s1) = if d > 0 { (lo - a, hi - a) } else { (hi - a, lo - a) };
+        first = max(first, mul_div(s0, n, d, Round::Up));
+        last  = min(last,  mul_div(s1, n, d, Round::Down));
+    }
+    if first > last {
+        return None
+    }
+
+    let point = |m : i128| {
+        ((ax + mul_div(dx, m, n, Round::Nearest)) as isize, (ay + mul_div(dy, m, n, Round::Nearest)) as isize)
+    };
+    let (sx, sy) = point(first);
+    let (ex, ey) = point(last);
+    Some((sx, sy, ex, ey, first as u64))
+}
+
+#[derive(Clone, Copy)]
+enum Round {
+    Down,
+    Nearest,
+    Up
+}
+
+// a * b / d with b >= 0, rounded as requested and clamped to b + 1 in
+// absolute value. The product is computed on unsigned magnitudes, which
+// cannot overflow for differences of isize values.
+fn mul_div(a : i128, b : i128, d : i128, round : Round) -> i128 {
+    let negative = a != 0 && (a < 0) != (d < 0);
+    let p = a.unsigned_abs() * b.unsigned_abs();
+    let (q, r) = (p / d.unsigned_abs(), p % d.unsigned_abs());
+    let away = match round {
+        Round::Down    => negative,
+        Round::Up      => !negative,
+        Round::Nearest => 2 * r >= d.unsigned_abs()
+    };
+    let q = min(q + (away && r != 0) as u128, b.unsigned_abs() + 1) as i128;
+    if negative { -q } else { q }
+}
+
+// The integer square root, rounded to the nearest integer.
+fn isqrt(n : u64) -> u64 {
+    // Newton's method, from an initial value above the root.
+    let mut r = n;
+    let mut next = r.div_ceil(2);
+    while next < r {
+        r = next;
+        next = (r + n / r) / 2;
+    }
+    // Round up when n is closer to (r + 1)^2.
+    if n - r * r > r { r + 1 } else { r }
+}
@@ -0,0 +1,61 @@
+
+use crate::{PCD8544, PixelOp};
+
+/// How the pixels of a sprite combine with the content of the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlitMode {
+    /// Replace the pixels of the display.
+    Copy,
+    /// Turn on the pixels set in the sprite.
+    Or,
+    /// Turn off the pixels clear in the sprite.
+    And,
+    /// Toggle the pixels set in the sprite. Drawing twice at the same place
+    /// restores the display.
+    Xor
+}
+
+/// A 1 bit-per-pixel image.
+///
+/// `data` contains `height` rows of `ceil(width / 8)` bytes, where the most
+/// significant bit is the leftmost pixel, as in `draw_bitmap`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sprite {
+    pub width : usize,
+    pub height : usize,
+    pub data : Vec<u8>
+}
+
+impl Sprite {
+    pub fn new(width : usize, height : usize, data : Vec<u8>) -> Self {
+        Self { width, height, data }
+    }
+
+    /// The value of a pixel of the sprite, false outside of it.
+    pub fn get_pixel(&self, x : usize, y : usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false
+        }
+        let b = self.data.get(y * self.width.div_ceil(8) + x / 8).cloned().unwrap_or(0x00);
+        (b & (0x80 >> (x % 8))) != 0x00
+    }
+}
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Draw a sprite with its top-left corner at `(x, y)`.
+    pub fn draw_sprite(&mut self, x : usize, y : usize, sprite : &Sprite, mode : BlitMode) {
+        for r in 0..sprite.height {
+            for k in 0..sprite.width {
+                let op = match (mode, sprite.get_pixel(k, r)) {
+                    (BlitMode::Copy, true)  => PixelOp::Set,
+                    (BlitMode::Copy, false) => PixelOp::Clear,
+                    (BlitMode::Or,   true)  => PixelOp::Set,
+                    (BlitMode::And,  false) => PixelOp::Clear,
+                    (BlitMode::Xor,  true)  => PixelOp::Toggle,
+                    _                       => continue
+                };
+                self.set_pixel_op(x + k, y + r, op);
+            }
+        }
+    }
+}
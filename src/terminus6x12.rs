@@ -1,5 +1,5 @@
 
-use font::*;
+use crate::font::*;
 
 pub const WIDTH : usize = 6;
 
@@ -13392,3 +13392,22 @@ pub static ENCODING : [u16 ; CHARS] = [
     65533,
 	9208,
 ];
+
+/// The Terminus font, 6x12 pixels.
+pub struct Terminus6x12;
+
+impl Font for Terminus6x12 {
+    fn width(&self) -> usize {
+        WIDTH
+    }
+
+    fn height(&self) -> usize {
+        HEIGHT
+    }
+
+    fn glyph(&self, c : char) -> Option<&[u8]> {
+        ENCODING.iter()
+            .position(|&v| v as u32 == c as u32)
+            .map(|i| &BITMAP[i * HEIGHT .. (i + 1) * HEIGHT])
+    }
+}
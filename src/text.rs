@@ -0,0 +1,411 @@
+
+use core::cmp::min;
+use core::fmt;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use crate::{PCD8544, Result, TAB_STOP};
+
+/// The position of the text cursor, in characters, used by `write!`,
+/// `println` and `tick_cursor`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cursor {
+    pub col : usize,
+    pub row : usize,
+    /// Whether the cursor block drawn by `tick_cursor` is shown.
+    pub visible : bool
+}
+
+/// The direction in which `print` advances between characters.
+///
+/// Only the order of the characters changes: the glyphs themselves are not
+/// rotated or mirrored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextDirection {
+    /// From left to right, wrapping to the next row.
+    LeftRight,
+    /// From right to left, wrapping to the last column of the next row.
+    RightLeft,
+    /// From top to bottom, wrapping to the top of the next column.
+    TopBottom
+}
+
+// Split a string after at most `cols` characters, preferably at a space
+// or at a newline. Returns the first line and the rest of the string,
+// without the spaces at the line break.
+fn split_line(s : &str, cols : usize) -> (&str, &str) {
+    let mut space = None;
+    for (n, (i, c)) in s.char_indices().enumerate() {
+        if c == '\n' {
+            return (&s[..i], &s[i + 1..]);
+        }
+        if n == cols && c == ' ' {
+            return (&s[..i], s[i..].trim_start_matches(' '));
+        }
+        if n == cols {
+            return match space {
+                Some(j) => (&s[..j], s[j..].trim_start_matches(' ')),
+                // Hard-break words that are longer than a line.
+                None    => (&s[..i], &s[i..])
+            };
+        }
+        if c == ' ' {
+            space = Some(i);
+        }
+    }
+    (s, "")
+}
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Set the direction of the text written with `print`, `print_scaled`
+    /// and `print_opaque`.
+    ///
+    /// In the vertical direction, a newline moves to the next column and
+    /// returns to the first row of the string, and characters are spaced by
+    /// the full cell width even in proportional mode. The other text
+    /// functions, including the console, always print from left to right.
+    pub fn set_text_direction(&mut self, direction : TextDirection) {
+        self.direction = direction;
+    }
+
+    // Same as print_from, from right to left: the first character is drawn
+    // in cell (x, y) and newlines return to column `margin`.
+    pub(crate) fn print_right_left(&mut self, x : usize, y : usize, margin : usize, s : &str, scale : usize, opaque : bool) -> (usize, usize) {
+        let (width, height) = self.dimensions();
+        let cell_width  = (self.font.width() + self.char_spacing) * scale;
+        let cell_height = self.font.height() * scale;
+        let tab_width = TAB_STOP * cell_width;
+        let line_end = (width / cell_width) * cell_width;
+        if line_end == 0 {
+            return (x, y)
+        }
+        // The right edge of the next character.
+        let mut xp = min((x + 1) * cell_width, line_end);
+        let mut yc = y;
+        for c in s.chars() {
+            match c {
+                '\n' => {
+                    xp = min((margin + 1) * cell_width, line_end);
+                    yc += 1;
+                }
+                '\r' => xp = min((margin + 1) * cell_width, line_end),
+                '\t' => xp = xp.saturating_sub(1) / tab_width * tab_width,
+                _    => {
+                    let advance = self.glyph_advance(c) * scale;
+                    if xp < advance {
+                        xp = line_end;
+                        yc += 1;
+                        if yc * cell_height >= height {
+                            break;
+                        }
+                    }
+                    xp -= advance;
+                    if opaque {
+                        self.clear_region(xp, yc * cell_height, advance, cell_height);
+                    }
+                    self.draw_char_at(xp, yc * cell_height, c, scale);
+                }
+            }
+            if xp == 0 {
+                xp = line_end;
+                yc += 1;
+            }
+            if yc * cell_height >= height {
+                break;
+            }
+        }
+        match (xp / cell_width).checked_sub(1) {
+            Some(col) => (col, yc),
+            None      => (line_end / cell_width - 1, yc + 1)
+        }
+    }
+
+    // Same as print_from, from top to bottom: newlines move to the next
+    // column and return to row `y`.
+    pub(crate) fn print_top_bottom(&mut self, x : usize, y : usize, s : &str, scale : usize, opaque : bool) -> (usize, usize) {
+        let (width, height) = self.dimensions();
+        let cell_width  = (self.font.width() + self.char_spacing) * scale;
+        let cell_height = self.font.height() * scale;
+        let mut xc = x;
+        let mut yc = y;
+        for c in s.chars() {
+            match c {
+                '\n' => {
+                    xc += 1;
+                    yc = y;
+                }
+                '\r' => yc = y,
+                '\t' => yc = (yc / TAB_STOP + 1) * TAB_STOP,
+                _    => {
+                    if opaque {
+                        self.clear_region(xc * cell_width, yc * cell_height, cell_width, cell_height);
+                    }
+                    self.draw_char_at(xc * cell_width, yc * cell_height, c, scale);
+                    yc += 1;
+                }
+            }
+            if yc * cell_height >= height {
+                xc += 1;
+                yc = 0;
+            }
+            if xc * cell_width >= width {
+                break;
+            }
+        }
+        (xc, yc)
+    }
+
+    /// Set the position, in characters, of the text written with `write!`.
+    ///
+    /// The cursor block drawn by `tick_cursor` is removed.
+    pub fn set_cursor(&mut self, col : usize, row : usize) {
+        self.hide_cursor();
+        self.cursor.col = col;
+        self.cursor.row = row;
+    }
+
+    /// The position of the text cursor, and whether its block is shown.
+    pub fn cursor(&self) -> Cursor {
+        self.cursor
+    }
+
+    /// Move the text cursor, keeping its block shown if it was.
+    pub fn move_cursor(&mut self, col : usize, row : usize) {
+        let visible = self.cursor.visible;
+        self.set_cursor(col, row);
+        if visible {
+            self.tick_cursor();
+        }
+    }
+
+    /// Show or hide an inverted block on the character cell of the text
+    /// cursor, for a blinking cursor when called at a steady rate, such as
+    /// every 500 ms.
+    ///
+    /// The cursor follows the text written with `write!` and `println`: the
+    /// block is removed before printing and appears after the last printed
+    /// character on the next call. If the cursor is past the last row, the
+    /// display scrolls up as it would for the next character.
+    pub fn tick_cursor(&mut self) {
+        if !self.cursor.visible && !self.scroll_to_cursor() {
+            return
+        }
+        self.invert_cursor_cell();
+        self.cursor.visible = !self.cursor.visible;
+    }
+
+    // Remove the block drawn by tick_cursor, if it is shown.
+    fn hide_cursor(&mut self) {
+        if self.cursor.visible {
+            self.invert_cursor_cell();
+            self.cursor.visible = false;
+        }
+    }
+
+    fn invert_cursor_cell(&mut self) {
+        let cell_width = self.font.width() + self.char_spacing;
+        let cell_height = self.font.height();
+        self.invert_region(self.cursor.col * cell_width, self.cursor.row * cell_height, cell_width, cell_height);
+    }
+
+    // Scroll the display up until the cursor is on the last row, if it is
+    // past it. Returns false if the font is taller than the display.
+    fn scroll_to_cursor(&mut self) -> bool {
+        let font_height = self.font.height();
+        let rows = self.text_rows();
+        if rows == 0 {
+            return false
+        }
+        if self.cursor.row >= rows {
+            self.scroll_vertical(-(((self.cursor.row + 1 - rows) * font_height) as i32));
+            self.cursor.row = rows - 1;
+        }
+        true
+    }
+
+    /// Print a string at the cursor position, then move the cursor to the
+    /// beginning of the next row.
+    pub fn println(&mut self, s : &str) {
+        self.print_at_cursor(s);
+        self.print_at_cursor("\n");
+    }
+
+    // Print a string at the cursor position, scrolling the display up
+    // when the text goes past the last row.
+    fn print_at_cursor(&mut self, s : &str) {
+        self.hide_cursor();
+        let mut buf = [0u8 ; 4];
+        for c in s.chars() {
+            if !self.scroll_to_cursor() {
+                return
+            }
+            let (col, row) = self.print_left_right(self.cursor.col, self.cursor.row, 0, c.encode_utf8(&mut buf), 1, false);
+            self.cursor.col = col;
+            self.cursor.row = row;
+        }
+    }
+
+    /// Print a string as `print` does, turning off the pixels of each
+    /// character cell before drawing the glyph, including the spacing.
+    ///
+    /// This overwrites the previous text without clearing the line first.
+    pub fn print_opaque(&mut self, x : usize, y : usize, s : &str) {
+        self.print_from(x, y, x, s, 1, true);
+    }
+
+    /// Print a string as `print_opaque` does, in negative: the pixels of each
+    /// character cell are turned on and the glyph is drawn with pixels off.
+    ///
+    /// With `inverse` set, the text is drawn as `print_opaque` would draw it
+    /// without `inverse`.
+    pub fn print_inverse(&mut self, x : usize, y : usize, s : &str) {
+        self.inverse = !self.inverse;
+        self.print_from(x, y, x, s, 1, true);
+        self.inverse = !self.inverse;
+    }
+
+    /// Print a string in a column of text, `width_chars` characters wide,
+    /// breaking lines at spaces when possible.
+    ///
+    /// Returns the part of the string that did not fit on the display.
+    pub fn print_wrapped<'a>(&mut self, x : usize, y : usize, width_chars : usize, s : &'a str) -> &'a str {
+        let cols = min(width_chars, self.text_cols().saturating_sub(x));
+        let rows = self.text_rows().saturating_sub(y);
+        if cols == 0 {
+            return s
+        }
+
+        let mut rest = s;
+        for r in 0..rows {
+            if rest.is_empty() {
+                break;
+            }
+            let (line, next) = split_line(rest, cols);
+            for (k, c) in line.chars().enumerate() {
+                self.print_char(x + k, y + r, c);
+            }
+            rest = next;
+        }
+        rest
+    }
+
+    /// Print a string in a rectangle of `w` by `h` pixels, breaking lines
+    /// at spaces when possible.
+    ///
+    /// As many lines as fit entirely in the rectangle are drawn. If the
+    /// text does not fit, the last line ends with "...". Glyphs are cut at
+    /// the right edge of the rectangle, so no pixel outside it is modified.
+    pub fn draw_text_in_box(&mut self, x : usize, y : usize, w : usize, h : usize, s : &str) {
+        let font_height = self.font.height();
+        let rows = h / font_height;
+        let ellipsis = "...";
+
+        let mut rest = s;
+        for r in 0..rows {
+            if rest.is_empty() {
+                break;
+            }
+            let (mut line, next) = self.split_line_pixels(rest, w);
+            let truncated = r == rows - 1 && !next.is_empty();
+            if truncated {
+                let room = w.saturating_sub(self.measure_text(ellipsis));
+                while self.measure_text(line) > room {
+                    let mut chars = line.chars();
+                    chars.next_back();
+                    line = chars.as_str();
+                }
+                line = line.trim_end_matches(' ');
+            }
+            let yp = y + r * font_height;
+            let xp = self.print_in_width(x, yp, x + w, line);
+            if truncated {
+                self.print_in_width(xp, yp, x + w, ellipsis);
+            }
+            rest = next;
+        }
+    }
+
+    // Split a string after at most `w` pixels, preferably at a space or at
+    // a newline, as split_line does. At least one character is taken, so
+    // that the text always progresses.
+    fn split_line_pixels<'a>(&self, s : &'a str, w : usize) -> (&'a str, &'a str) {
+        let mut xp = 0;
+        let mut space = None;
+        for (i, c) in s.char_indices() {
+            if c == '\n' {
+                return (&s[..i], &s[i + 1..]);
+            }
+            let advance = self.glyph_advance(c);
+            if i > 0 && xp + min(advance, self.font.width()) > w {
+                if c == ' ' {
+                    return (&s[..i], s[i..].trim_start_matches(' '));
+                }
+                return match space {
+                    Some(j) => (&s[..j], s[j..].trim_start_matches(' ')),
+                    // Hard-break words that are longer than a line.
+                    None    => (&s[..i], &s[i..])
+                };
+            }
+            if c == ' ' {
+                space = Some(i);
+            }
+            xp += advance;
+        }
+        (s, "")
+    }
+
+    // Print a string on a single line from the given pixel coordinates,
+    // without modifying the pixels from column `right` on. Returns the
+    // pixel position after the last character.
+    fn print_in_width(&mut self, xp : usize, yp : usize, right : usize, s : &str) -> usize {
+        let mut x = xp;
+        for c in s.chars() {
+            if x >= right {
+                break;
+            }
+            self.draw_char_clipped(x as isize, yp as isize, c, 1, right);
+            x += self.glyph_advance(c);
+        }
+        x
+    }
+}
+
+impl<SPI, DC, RST, BL, SpiE, PinE> PCD8544<SPI, DC, RST, BL>
+    where SPI : spi::Write<u8, Error = SpiE>,
+          DC  : OutputPin<Error = PinE>,
+          RST : OutputPin<Error = PinE> {
+
+    /// Clear the display, print a string as `print` does and update the
+    /// display.
+    pub fn show_text(&mut self, x : usize, y : usize, s : &str) -> Result<(), SpiE, PinE> {
+        self.clear();
+        self.print(x, y, s);
+        self.update()
+    }
+
+    /// Clear the display, print each string on its own row, and update the
+    /// display.
+    ///
+    /// The lines that do not fit are ignored: with the default font, the
+    /// display has 4 rows in landscape orientation. Long lines are cut.
+    pub fn show_lines(&mut self, lines : &[&str]) -> Result<(), SpiE, PinE> {
+        self.clear();
+        let font_height = self.font.height();
+        let rows = self.text_rows();
+        for (r, line) in lines.iter().take(rows).enumerate() {
+            self.print_at(0, r * font_height, line);
+        }
+        self.update()
+    }
+}
+
+/// Print text at the cursor position, as set by `set_cursor`.
+///
+/// The cursor advances and wraps as in `print`, and a newline moves it to
+/// the beginning of the next row. The display scrolls up when the text goes
+/// past the last row.
+impl<SPI, DC, RST, BL> fmt::Write for PCD8544<SPI, DC, RST, BL> {
+    fn write_str(&mut self, s : &str) -> fmt::Result {
+        self.print_at_cursor(s);
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+
+use crate::{PCD8544, Orientation, LCDWIDTH, LCDHEIGHT, ROWPIXELS, BUFFER_LEN};
+
+const ROWS : usize = LCDHEIGHT / ROWPIXELS;
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Move the content of the display down by the given number of pixels,
+    /// or up if `pixels` is negative.
+    ///
+    /// The vacated area is filled with pixels off.
+    pub fn scroll_vertical(&mut self, pixels : i32) {
+        let d = pixels as isize;
+        match self.orient {
+            Orientation::Landscape(false) => self.shift_native_y(d),
+            Orientation::Landscape(true)  => self.shift_native_y(-d),
+            Orientation::Portrait(false)  => self.shift_native_x(-d),
+            Orientation::Portrait(true)   => self.shift_native_x(d)
+        }
+    }
+
+    /// Move the content of the display right by the given number of pixels,
+    /// or left if `pixels` is negative.
+    ///
+    /// The vacated area is filled with pixels off.
+    pub fn scroll_horizontal(&mut self, pixels : i32) {
+        let d = pixels as isize;
+        match self.orient {
+            Orientation::Landscape(false) => self.shift_native_x(d),
+            Orientation::Landscape(true)  => self.shift_native_x(-d),
+            Orientation::Portrait(false)  => self.shift_native_y(d),
+            Orientation::Portrait(true)   => self.shift_native_y(-d)
+        }
+    }
+
+    /// Flip the content of the display left to right.
+    pub fn mirror_horizontal(&mut self) {
+        match self.orient {
+            Orientation::Landscape(_) => self.mirror_native_x(),
+            Orientation::Portrait(_)  => self.mirror_native_y()
+        }
+    }
+
+    /// Flip the content of the display upside down.
+    pub fn mirror_vertical(&mut self) {
+        match self.orient {
+            Orientation::Landscape(_) => self.mirror_native_y(),
+            Orientation::Portrait(_)  => self.mirror_native_x()
+        }
+    }
+
+    /// Change the orientation of the display, keeping the current image in
+    /// logical coordinates: the image rotates on the panel.
+    ///
+    /// Switching between landscape and portrait crops the parts of the image
+    /// that do not fit. This reads and writes every pixel of the display.
+    pub fn set_orientation(&mut self, orient : Orientation) {
+        let old = self.buffer;
+        let old_orient = self.orient;
+        let fill = if self.inverse { 0xFF } else { 0x00 };
+        self.buffer = [fill ; BUFFER_LEN];
+        self.orient = orient;
+
+        let (width, height) = old_orient.dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                if let (Some((i, bit)), Some((j, new_bit))) = (old_orient.location(x, y), orient.location(x, y)) {
+                    if old[i] & bit != 0 {
+                        self.buffer[j] |= new_bit;
+                    }
+                    else {
+                        self.buffer[j] &= !new_bit;
+                    }
+                }
+            }
+        }
+        self.dirty = Some((0, BUFFER_LEN - 1));
+    }
+
+    // Reverse the order of the columns in native coordinates.
+    fn mirror_native_x(&mut self) {
+        for row in self.buffer.chunks_mut(LCDWIDTH) {
+            row.reverse();
+        }
+        self.dirty = Some((0, BUFFER_LEN - 1));
+    }
+
+    // Reverse the order of the pixel rows in native coordinates.
+    fn mirror_native_y(&mut self) {
+        for x in 0..LCDWIDTH {
+            // Gather the column in a single word, with bit n for pixel row n.
+            let mut col : u64 = 0;
+            for r in 0..ROWS {
+                col |= (self.buffer[x + r * LCDWIDTH] as u64) << (r * ROWPIXELS);
+            }
+
+            col = col.reverse_bits() >> (64 - LCDHEIGHT);
+
+            for r in 0..ROWS {
+                self.buffer[x + r * LCDWIDTH] = (col >> (r * ROWPIXELS)) as u8;
+            }
+        }
+        self.dirty = Some((0, BUFFER_LEN - 1));
+    }
+
+    // Move the buffer content by d columns in native coordinates.
+    fn shift_native_x(&mut self, d : isize) {
+        let fill = if self.inverse { 0xFF } else { 0x00 };
+        for row in self.buffer.chunks_mut(LCDWIDTH) {
+            if d.unsigned_abs() >= LCDWIDTH {
+                for b in row.iter_mut() {
+                    *b = fill;
+                }
+            }
+            else if d > 0 {
+                let n = d as usize;
+                row.copy_within(0 .. LCDWIDTH - n, n);
+                for b in row[..n].iter_mut() {
+                    *b = fill;
+                }
+            }
+            else if d < 0 {
+                let n = (-d) as usize;
+                row.copy_within(n .. LCDWIDTH, 0);
+                for b in row[LCDWIDTH - n..].iter_mut() {
+                    *b = fill;
+                }
+            }
+        }
+        self.dirty = Some((0, BUFFER_LEN - 1));
+    }
+
+    // Move the buffer content by d pixel rows in native coordinates.
+    fn shift_native_y(&mut self, d : isize) {
+        let mask : u64 = (1 << LCDHEIGHT) - 1;
+        let fill = if self.inverse { mask } else { 0 };
+        for x in 0..LCDWIDTH {
+            // Gather the column in a single word, with bit n for pixel row n.
+            let mut col : u64 = 0;
+            for r in 0..ROWS {
+                col |= (self.buffer[x + r * LCDWIDTH] as u64) << (r * ROWPIXELS);
+            }
+
+            col = if d.unsigned_abs() >= LCDHEIGHT {
+                fill
+            }
+            else if d >= 0 {
+                let n = d as usize;
+                ((col << n) | (fill & ((1 << n) - 1))) & mask
+            }
+            else {
+                let n = (-d) as usize;
+                (col >> n) | (fill & !(mask >> n))
+            };
+
+            for r in 0..ROWS {
+                self.buffer[x + r * LCDWIDTH] = (col >> (r * ROWPIXELS)) as u8;
+            }
+        }
+        self.dirty = Some((0, BUFFER_LEN - 1));
+    }
+}
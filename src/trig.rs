@@ -0,0 +1,35 @@
+
+// Integer trigonometry, for drawing without floating point.
+
+/// The scale of the values returned by `sin` and `cos`.
+pub const ONE : i32 = 1024;
+
+// sin(d) * ONE for d in 0..=90 degrees.
+const SIN_TABLE : [i32 ; 91] = [
+       0,   18,   36,   54,   71,   89,  107,  125,  143,  160,
+     178,  195,  213,  230,  248,  265,  282,  299,  316,  333,
+     350,  367,  384,  400,  416,  433,  449,  465,  481,  496,
+     512,  527,  543,  558,  573,  587,  602,  616,  630,  644,
+     658,  672,  685,  698,  711,  724,  737,  749,  761,  773,
+     784,  796,  807,  818,  828,  839,  849,  859,  868,  878,
+     887,  896,  904,  912,  920,  928,  935,  943,  949,  956,
+     962,  968,  974,  979,  984,  989,  994,  998, 1002, 1005,
+    1008, 1011, 1014, 1016, 1018, 1020, 1022, 1023, 1023, 1024,
+    1024
+];
+
+/// The sine of an angle in degrees, multiplied by `ONE`.
+pub fn sin(deg : i32) -> i32 {
+    let d = deg.rem_euclid(360) as usize;
+    match d {
+        0   ..= 90  =>  SIN_TABLE[d],
+        91  ..= 180 =>  SIN_TABLE[180 - d],
+        181 ..= 270 => -SIN_TABLE[d - 180],
+        _           => -SIN_TABLE[360 - d]
+    }
+}
+
+/// The cosine of an angle in degrees, multiplied by `ONE`.
+pub fn cos(deg : i32) -> i32 {
+    sin(deg + 90)
+}
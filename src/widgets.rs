@@ -0,0 +1,290 @@
+
+use core::cmp::{min, max};
+use crate::{PCD8544, trig};
+
+/// The frame drawn around a plot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlotFrame {
+    None,
+    /// A rectangle around the plot area.
+    Border,
+    /// Lines along the left and bottom edges of the plot area.
+    Axes
+}
+
+// The segments lit for each digit, with bit 0 for segment a (top)
+// to bit 6 for segment g (middle), clockwise.
+const SEGMENTS : [u8 ; 10] = [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F];
+const SEGMENTS_MINUS : u8 = 0x40;
+
+// The angles of the ends of a gauge scale, counterclockwise from the right.
+const GAUGE_START_DEG : i32 = 210;
+const GAUGE_SPAN_DEG  : i32 = 240;
+
+impl<SPI, DC, RST, BL> PCD8544<SPI, DC, RST, BL> {
+    /// Draw a horizontal progress bar, filled from the left in proportion
+    /// to `fraction`, clamped to `0.0 ..= 1.0`.
+    ///
+    /// The unfilled part is cleared, so that the bar can be redrawn in place.
+    /// With `border`, the bar is surrounded by a one-pixel outline.
+    pub fn draw_progress_bar(&mut self, x : usize, y : usize, w : usize, h : usize, fraction : f32, border : bool) {
+        let (x, y, w, h) = if border {
+            if w < 3 || h < 3 {
+                return self.fill_region(x, y, w, h, true)
+            }
+            self.draw_rect(x, y, w, h, true);
+            (x + 1, y + 1, w - 2, h - 2)
+        }
+        else {
+            (x, y, w, h)
+        };
+
+        // NaN gives an empty bar.
+        let f = if fraction > 1.0 { 1.0 } else if fraction > 0.0 { fraction } else { 0.0 };
+        let filled = min((w as f32 * f + 0.5) as usize, w);
+        self.fill_region(x, y, filled, h, true);
+        self.clear_region(x + filled, y, w - filled, h);
+    }
+
+    /// Plot a series of samples as connected lines in a box.
+    ///
+    /// The range of the samples is scaled to the height of the box, and equal
+    /// samples give a flat line in the middle. The samples are stretched or
+    /// decimated to the width of the box. The box is not cleared first.
+    #[allow(clippy::too_many_arguments)]
+    pub fn plot_series(&mut self, x : usize, y : usize, w : usize, h : usize, samples : &[i32], frame : PlotFrame) {
+        if w == 0 || h == 0 {
+            return
+        }
+        let (x, y, w, h) = match frame {
+            PlotFrame::None => (x, y, w, h),
+            PlotFrame::Border => {
+                self.draw_rect(x, y, w, h, true);
+                (x + 1, y + 1, w.saturating_sub(2), h.saturating_sub(2))
+            }
+            PlotFrame::Axes => {
+                self.vline(x, y, h, true);
+                self.hline(x, y + h - 1, w, true);
+                (x + 1, y, w - 1, h - 1)
+            }
+        };
+        if w == 0 || h == 0 || samples.is_empty() {
+            return
+        }
+
+        let lo = *samples.iter().min().unwrap() as i64;
+        let hi = *samples.iter().max().unwrap() as i64;
+        let n = samples.len();
+        let count = min(n, w);
+
+        let point = |j : usize| {
+            let (k, px) = if count > 1 {
+                (j * (n - 1) / (count - 1), j * (w - 1) / (count - 1))
+            }
+            else {
+                (0, 0)
+            };
+            let py = if hi > lo {
+                ((hi - samples[k] as i64) * (h as i64 - 1) / (hi - lo)) as usize
+            }
+            else {
+                h / 2
+            };
+            (x + px, y + py)
+        };
+
+        let (mut x0, mut y0) = point(0);
+        self.set_pixel(x0, y0, true);
+        for j in 1..count {
+            let (x1, y1) = point(j);
+            self.draw_line(x0, y0, x1, y1, true);
+            x0 = x1;
+            y0 = y1;
+        }
+    }
+
+    /// Draw a bar chart of `values` in a `w` by `h` area, scaled so that the
+    /// largest value fills the height.
+    ///
+    /// The width is divided evenly between the bars, separated by 1-pixel
+    /// gaps when there is room for them. The area is cleared first, and stays
+    /// blank when all values are zero.
+    pub fn draw_bars(&mut self, x : usize, y : usize, w : usize, h : usize, values : &[u32]) {
+        self.clear_region(x, y, w, h);
+        let hi = values.iter().cloned().max().unwrap_or(0) as u64;
+        if hi == 0 || w == 0 {
+            return
+        }
+        let n = values.len();
+        for (k, &v) in values.iter().enumerate() {
+            // Each slot ends with the gap to the next bar. With more bars
+            // than pixels, the last slots are clamped to the area.
+            let start = min(k * (w + 1) / n, w - 1);
+            let end = (k + 1) * (w + 1) / n;
+            let bw = min(max(end.saturating_sub(start + 1), 1), w - start);
+            let bh = ((v as u64 * h as u64 + hi / 2) / hi) as usize;
+            self.fill_region(x + start, y + h - bh, bw, bh, true);
+        }
+    }
+
+    /// Draw a digit as seven segments in a `w` by `h` cell.
+    ///
+    /// `digit` is a value from 0 to 9, or `b'-'` for a minus sign.
+    /// Other values give a blank cell. The cell is cleared first.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_seven_segment(&mut self, x : usize, y : usize, w : usize, h : usize, digit : u8, thickness : usize) {
+        self.clear_region(x, y, w, h);
+        let segments = match digit {
+            0 ..= 9 => SEGMENTS[digit as usize],
+            b'-'    => SEGMENTS_MINUS,
+            _       => 0
+        };
+        let t = min(thickness, min(w, h) / 3);
+        if t == 0 {
+            return
+        }
+        // Top of the middle segment.
+        let mid = (h - t) / 2;
+        let rects = [
+            (x,         y,           w, t),
+            (x + w - t, y,           t, mid + t),
+            (x + w - t, y + mid,     t, h - mid),
+            (x,         y + h - t,   w, t),
+            (x,         y + mid,     t, h - mid),
+            (x,         y,           t, mid + t),
+            (x,         y + mid,     w, t)
+        ];
+        for (k, &(rx, ry, rw, rh)) in rects.iter().enumerate() {
+            if segments & (1 << k) != 0 {
+                self.fill_region(rx, ry, rw, rh, true);
+            }
+        }
+    }
+
+    /// Draw a number with seven-segment digits, separated by `thickness` pixels.
+    ///
+    /// Returns the horizontal position after the last digit.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_number(&mut self, x : usize, y : usize, value : i32, w : usize, h : usize, thickness : usize) -> usize {
+        self.draw_number_padded(x, y, value, 1, w, h, thickness)
+    }
+
+    /// Draw a number as `draw_number` does, with leading zeros up to
+    /// `min_digits` digits, such as the minutes of a clock.
+    ///
+    /// At most 10 digits are drawn, without the sign.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_number_padded(&mut self, x : usize, y : usize, value : i32, min_digits : usize, w : usize, h : usize, thickness : usize) -> usize {
+        // Digits from the least significant, followed by the sign.
+        let mut digits = [0u8 ; 11];
+        let mut n = 0;
+        let mut v = value.unsigned_abs();
+        loop {
+            digits[n] = (v % 10) as u8;
+            n += 1;
+            v /= 10;
+            if v == 0 && n >= min(min_digits, 10) {
+                break;
+            }
+        }
+        if value < 0 {
+            digits[n] = b'-';
+            n += 1;
+        }
+
+        let mut px = x;
+        for &d in digits[..n].iter().rev() {
+            self.draw_seven_segment(px, y, w, h, d, thickness);
+            px += w + thickness;
+        }
+        px - thickness
+    }
+
+    /// Draw a colon between seven-segment digits of height `h`, as two
+    /// square dots of `thickness` pixels, such as in a clock.
+    ///
+    /// The column of the colon, `thickness` pixels wide, is cleared first
+    /// so that it can blink. Returns the horizontal position after it.
+    pub fn draw_colon(&mut self, x : usize, y : usize, h : usize, thickness : usize, visible : bool) -> usize {
+        self.clear_region(x, y, thickness, h);
+        if visible {
+            for k in [1, 2] {
+                let dy = (k * h / 3).saturating_sub(thickness / 2);
+                self.fill_region(x, y + dy, thickness, thickness, true);
+            }
+        }
+        x + thickness
+    }
+
+    /// Draw a battery gauge: an outline with a terminal on the right side,
+    /// filled in proportion to `fraction` as in `draw_progress_bar`.
+    ///
+    /// With `charging`, a lightning bolt is drawn in inverse video in the
+    /// middle of the battery.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_battery(&mut self, x : usize, y : usize, w : usize, h : usize, fraction : f32, charging : bool) {
+        if w < 6 || h < 5 {
+            return self.draw_progress_bar(x, y, w, h, fraction, false)
+        }
+        // The terminal is a small rectangle, centered vertically.
+        let nub_w = max(1, w / 10);
+        let nub_h = max(1, h / 2);
+        let body_w = w - nub_w;
+        self.draw_progress_bar(x, y, body_w, h, fraction, true);
+        self.clear_region(x + body_w, y, nub_w, h);
+        self.fill_region(x + body_w, y + (h - nub_h) / 2, nub_w, nub_h, true);
+
+        if !charging {
+            return
+        }
+        // Two strokes from the upper right to the lower left,
+        // joined by a horizontal step in the middle.
+        let (iw, ih) = (body_w - 2, h - 2);
+        let cx = (x + 1 + iw / 2) as isize;
+        let a = max(1, iw / 6) as isize;
+        let mid = ih / 2;
+        for r in 0..ih {
+            let py = y + 1 + r;
+            if r == mid {
+                for px in cx - a ..= cx + a {
+                    self.toggle_pixel(px as usize, py);
+                }
+                continue;
+            }
+            let (k, len) = if r < mid { (r, mid) } else { (r - mid, ih - 1 - mid) };
+            let off = a - 2 * a * k as isize / max(len, 1) as isize;
+            self.toggle_pixel((cx + off) as usize, py);
+        }
+    }
+
+    /// Draw an analog gauge: a 240 degree arc open at the bottom, with tick
+    /// marks at each quarter and a needle pointing at `fraction` of the
+    /// scale, clamped to `0.0 ..= 1.0`, from the lower left.
+    ///
+    /// The disc of the dial is cleared first, so that the gauge can be
+    /// redrawn in place.
+    pub fn draw_gauge(&mut self, cx : usize, cy : usize, radius : usize, fraction : f32) {
+        let f = if fraction > 1.0 { 1.0 } else if fraction > 0.0 { fraction } else { 0.0 };
+        self.fill_circle(cx, cy, radius, false);
+        self.draw_arc(cx, cy, radius, GAUGE_START_DEG - GAUGE_SPAN_DEG, GAUGE_START_DEG, true);
+
+        let (x0, y0) = (cx as isize, cy as isize);
+        let point = |deg : i32, r : usize| {
+            (x0 + r as isize * trig::cos(deg) as isize / trig::ONE as isize,
+             y0 - r as isize * trig::sin(deg) as isize / trig::ONE as isize)
+        };
+        let tick = radius / 5;
+        if tick > 0 {
+            for k in 0..5 {
+                let deg = GAUGE_START_DEG - k * GAUGE_SPAN_DEG / 4;
+                let (xa, ya) = point(deg, radius - tick);
+                let (xb, yb) = point(deg, radius);
+                self.line_signed(xa, ya, xb, yb, true);
+            }
+        }
+
+        let deg = GAUGE_START_DEG - (f * GAUGE_SPAN_DEG as f32 + 0.5) as i32;
+        let (x1, y1) = point(deg, radius.saturating_sub(tick + 1));
+        self.line_signed(x0, y0, x1, y1, true);
+    }
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "std")]
+
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+use pcd8544::PCD8544Array;
+
+#[test]
+fn array_routes_pixels() {
+    let mut array = PCD8544Array::new(vec![common::display(), common::display()], 2);
+    assert_eq!((array.width(), array.height()), (168, 48));
+
+    array.set_pixel(83, 10, true);
+    array.set_pixel(84, 10, true);
+    array.set_pixel(168, 10, true);
+    assert!(array.get_pixel(84, 10));
+    assert!(array.panels()[0].get_pixel(83, 10));
+    assert!(array.panels()[1].get_pixel(0, 10));
+    assert!(!array.get_pixel(168, 10));
+    array.update().unwrap();
+}
+
+#[test]
+fn array_draws_across_the_seam() {
+    let mut array = PCD8544Array::new(vec![common::display(), common::display()], 2);
+    array.draw_line(0, 0, 167, 47, true);
+    assert!(array.get_pixel(0, 0) && array.get_pixel(167, 47));
+    // The line is continuous at the seam.
+    assert!((0..48).any(|y| array.get_pixel(83, y)));
+    assert!((0..48).any(|y| array.get_pixel(84, y)));
+
+    // With 8-pixel cells, the 11th character covers x = 80 to 85.
+    let mut lcd = common::display();
+    lcd.char_spacing = 2;
+    let mut array = PCD8544Array::new(vec![lcd, common::display()], 2);
+    array.print(10, 0, "#");
+    assert!((80..84).any(|x| (0..12).any(|y| array.panels()[0].get_pixel(x, y))));
+    assert!((0..2).any(|x| (0..12).any(|y| array.panels()[1].get_pixel(x, y))));
+}
+
+#[test]
+fn array_prints_on_each_panel() {
+    // Two rows of one panel each: with 12-pixel rows, the 5th text row
+    // starts on the second panel.
+    let mut array = PCD8544Array::new(vec![common::display(), common::display()], 1);
+    assert_eq!((array.width(), array.height()), (84, 96));
+    array.print(0, 4, "#");
+    assert!((0..6).any(|x| (0..12).any(|y| array.panels()[1].get_pixel(x, y))));
+    assert!(array.panels()[0].buffer().iter().all(|&b| b == 0x00));
+
+    // A character across the seam is the one drawn on a single panel.
+    let mut lcd = common::display();
+    lcd.draw_char_at(0, 0, '#', 1);
+    let mut first = common::display();
+    first.char_spacing = 2;
+    let mut array = PCD8544Array::new(vec![first, common::display()], 2);
+    array.print(10, 1, "#");
+    for y in 0..12 {
+        for x in 0..6 {
+            assert_eq!(array.get_pixel(80 + x, 12 + y), lcd.get_pixel(x, y), "({}, {})", x, y);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+#![cfg(all(feature = "async", feature = "mock"))]
+
+extern crate embedded_hal;
+extern crate pcd8544;
+extern crate tokio;
+
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use pcd8544::{PCD8544, BlockingSpi, MockPCD8544, Orientation};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+// A bus that records the threads its writes ran on.
+struct ThreadSpi(Arc<Mutex<Vec<(ThreadId, usize)>>>);
+
+impl spi::Write<u8> for ThreadSpi {
+    type Error = ();
+
+    fn write(&mut self, words : &[u8]) -> Result<(), ()> {
+        self.0.lock().unwrap().push((thread::current().id(), words.len()));
+        Ok(())
+    }
+}
+
+struct NoPin;
+
+impl OutputPin for NoPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), ()> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn update_async_sends_dirty_bytes() {
+    let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.set_max_transfer_bytes(100);
+    rt.block_on(async {
+        lcd.reset_async().await.unwrap();
+        lcd.update_async().await.unwrap();
+    });
+    assert_eq!(lcd.sent_data().len(), 504);
+
+    // The display memory is known after a full update.
+    #[cfg(feature = "diff-update")]
+    {
+        lcd.clear_sent();
+        lcd.update_diff().unwrap();
+        assert!(lcd.sent_data().is_empty());
+    }
+
+    lcd.clear_sent();
+
+    lcd.set_pixel(3, 9, true);
+    rt.block_on(lcd.update_async()).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x41, 0x83]);
+    assert_eq!(lcd.sent_data(), vec![0x02]);
+}
+
+#[test]
+fn blocking_spi_writes_outside_the_executor() {
+    let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut lcd = PCD8544::from_hal(BlockingSpi::new(ThreadSpi(log.clone())), NoPin, NoPin).unwrap();
+    log.lock().unwrap().clear();
+
+    rt.block_on(lcd.update_async()).unwrap();
+    let log = log.lock().unwrap();
+    assert_eq!(log.iter().map(|&(_, n)| n).sum::<usize>(), 2 + 504);
+    assert!(log.iter().all(|&(id, _)| id != thread::current().id()));
+}
+
+#[cfg(feature = "double-buffer")]
+#[test]
+fn update_async_sends_the_front_buffer() {
+    let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    rt.block_on(lcd.update_async()).unwrap();
+
+    lcd.draw_to_back();
+    lcd.set_pixel(3, 9, true);
+    lcd.clear_sent();
+    rt.block_on(lcd.update_async()).unwrap();
+    assert!(lcd.sent_data().is_empty());
+
+    lcd.draw_to_front();
+    rt.block_on(lcd.update_async()).unwrap();
+    let data = lcd.sent_data();
+    assert_eq!(data.len(), 504);
+    assert_eq!(data[87], 0x02);
+}
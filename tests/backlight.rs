@@ -0,0 +1,86 @@
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+use embedded_hal::PwmPin;
+use embedded_hal::digital::v2::OutputPin;
+use pcd8544::{BacklightPin, BacklightPwm};
+use std::cell::Cell;
+use std::rc::Rc;
+
+// A PWM output recording its duty cycle and whether it is enabled.
+struct RecordingPwm(Rc<Cell<(u16, bool)>>);
+
+impl PwmPin for RecordingPwm {
+    type Duty = u16;
+
+    fn disable(&mut self) {
+        self.0.set((self.0.get().0, false));
+    }
+
+    fn enable(&mut self) {
+        self.0.set((self.0.get().0, true));
+    }
+
+    fn get_duty(&self) -> u16 {
+        self.0.get().0
+    }
+
+    fn get_max_duty(&self) -> u16 {
+        1000
+    }
+
+    fn set_duty(&mut self, duty : u16) {
+        self.0.set((duty, self.0.get().1));
+    }
+}
+
+// An output pin recording its level.
+struct RecordingPin(Rc<Cell<bool>>);
+
+impl OutputPin for RecordingPin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), ()> {
+        self.0.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), ()> {
+        self.0.set(true);
+        Ok(())
+    }
+}
+
+#[test]
+fn backlight_pwm_levels() {
+    let state = Rc::new(Cell::new((0, false)));
+    let mut lcd = common::display().with_backlight(BacklightPwm(RecordingPwm(state.clone())));
+    lcd.set_backlight_level(0).unwrap();
+    assert_eq!(state.get(), (0, true));
+    lcd.set_backlight_level(51).unwrap();
+    assert_eq!(state.get(), (200, true));
+    lcd.set_backlight_level(128).unwrap();
+    assert_eq!(state.get().0, 501);
+    lcd.set_backlight(true).unwrap();
+    assert_eq!(state.get().0, 1000);
+    lcd.set_backlight(false).unwrap();
+    assert_eq!(state.get().0, 0);
+}
+
+#[test]
+fn backlight_pin_levels() {
+    let level = Rc::new(Cell::new(false));
+    let mut lcd = common::display().with_backlight(BacklightPin(RecordingPin(level.clone())));
+    lcd.set_backlight_level(1).unwrap();
+    assert!(level.get());
+    lcd.set_backlight_level(0).unwrap();
+    assert!(!level.get());
+    lcd.set_backlight(true).unwrap();
+    assert!(level.get());
+
+    // A missing backlight ignores the levels.
+    let mut lcd = common::display().with_backlight(None::<BacklightPin<RecordingPin>>);
+    assert!(lcd.set_backlight_level(100).is_ok());
+}
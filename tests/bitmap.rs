@@ -0,0 +1,179 @@
+#![cfg(feature = "std")]
+
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+#[test]
+fn draw_pbm_ascii_and_binary() {
+    let mut lcd = common::display();
+    lcd.draw_pbm(0, 0, b"P1\n# comment\n3 2\n1 0 1\n010\n").unwrap();
+    assert!(lcd.get_pixel(0, 0));
+    assert!(!lcd.get_pixel(1, 0));
+    assert!(lcd.get_pixel(1, 1));
+
+    lcd.draw_pbm(10, 0, b"P4 3 2\n\xA0\x40").unwrap();
+    assert!(lcd.get_pixel(10, 0));
+    assert!(!lcd.get_pixel(11, 0));
+    assert!(lcd.get_pixel(11, 1));
+}
+
+#[test]
+fn draw_pbm_malformed() {
+    let mut lcd = common::display();
+    assert!(lcd.draw_pbm(0, 0, b"P2 3 2\n").is_err());
+    assert!(lcd.draw_pbm(0, 0, b"P1 3\n").is_err());
+    assert!(lcd.draw_pbm(0, 0, b"P4 16 2\n\x00").is_err());
+}
+
+#[cfg(feature = "dither")]
+#[test]
+fn draw_gray_dithering() {
+    extern crate image;
+    use pcd8544::Dither;
+
+    let img = image::GrayImage::from_fn(16, 16, |x, _| image::Luma([if x < 8 { 0x20 } else { 0x80 }]));
+
+    let mut lcd = common::display();
+    lcd.draw_gray(0, 0, &img, Dither::Threshold(0x40));
+    assert!(lcd.get_pixel(0, 0));
+    assert!(!lcd.get_pixel(8, 0));
+
+    // Mid-gray gives about half of the pixels on.
+    for d in [Dither::FloydSteinberg, Dither::Ordered] {
+        let mut lcd = common::display();
+        lcd.draw_gray(0, 0, &img, d);
+        let on = (0..16).flat_map(|y| (8..16).map(move |x| (x, y))).filter(|&(x, y)| lcd.get_pixel(x, y)).count();
+        assert!((56..=72).contains(&on), "{:?}: {} pixels on", d, on);
+    }
+}
+
+#[cfg(feature = "dither")]
+#[test]
+fn draw_gray_temporal_cycles_planes() {
+    extern crate image;
+
+    // Level 2 of 3 is off in the first subframe and on in the next two.
+    let img = image::GrayImage::from_pixel(2, 2, image::Luma([0x55]));
+
+    let mut lcd = common::display();
+    lcd.draw_gray_temporal(0, 0, &img, 4);
+    assert!(!lcd.get_pixel(0, 0));
+    let seen : Vec<bool> = (0..3).map(|_| { lcd.tick_gray().unwrap(); lcd.get_pixel(1, 1) }).collect();
+    assert_eq!(seen, [true, true, false]);
+    assert!(!lcd.get_pixel(2, 0));
+
+    lcd.stop_gray();
+    lcd.tick_gray().unwrap();
+    assert!(!lcd.get_pixel(0, 0));
+}
+
+#[cfg(feature = "image-file")]
+#[test]
+fn draw_image_file_formats() {
+    extern crate image;
+    use pcd8544::Dither;
+    use std::path::Path;
+
+    let img = image::GrayImage::from_fn(4, 2, |x, _| image::Luma([if x < 2 { 0x00 } else { 0xFF }]));
+    let path = std::env::temp_dir().join("pcd8544-draw-image-file.bmp");
+    img.save(&path).unwrap();
+
+    let mut lcd = common::display();
+    lcd.draw_image_file(10, 10, &path, Dither::Threshold(0x80)).unwrap();
+    assert!(lcd.get_pixel(11, 11));
+    assert!(!lcd.get_pixel(12, 11));
+    std::fs::remove_file(&path).unwrap();
+
+    use pcd8544::Error;
+    match lcd.draw_image_file(0, 0, Path::new("/nonexistent.png"), Dither::Ordered) {
+        Err(Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        _                 => panic!("expected an I/O error")
+    }
+    let path = std::env::temp_dir().join("pcd8544-draw-image-file.png");
+    std::fs::write(&path, b"not an image").unwrap();
+    let res = lcd.draw_image_file(0, 0, &path, Dither::Ordered);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(res, Err(Error::Format)));
+}
+
+#[test]
+fn draw_sprite_modes() {
+    use pcd8544::{Sprite, BlitMode};
+
+    // A 3x2 sprite: X.X / .X.
+    let sprite = Sprite::new(3, 2, vec![0xA0, 0x40]);
+    let mut lcd = common::display();
+    lcd.fill_region(0, 0, 3, 1, true);
+
+    lcd.draw_sprite(0, 0, &sprite, BlitMode::And);
+    assert!(lcd.get_pixel(0, 0));
+    assert!(!lcd.get_pixel(1, 0));
+
+    lcd.draw_sprite(0, 0, &sprite, BlitMode::Or);
+    assert!(lcd.get_pixel(1, 1));
+
+    lcd.draw_sprite(0, 0, &sprite, BlitMode::Xor);
+    assert!(!lcd.get_pixel(0, 0));
+    assert!(!lcd.get_pixel(1, 1));
+
+    lcd.draw_sprite(82, 0, &sprite, BlitMode::Copy);
+    assert!(lcd.get_pixel(82, 0));
+    assert!(!lcd.get_pixel(83, 0));
+}
+
+#[test]
+fn test_pattern_border() {
+    use pcd8544::TestPattern;
+
+    let mut lcd = common::display();
+    lcd.fill_test_pattern(TestPattern::Border);
+    assert!(lcd.get_pixel(0, 0) && lcd.get_pixel(83, 0) && lcd.get_pixel(0, 47) && lcd.get_pixel(83, 47));
+    assert!(lcd.get_pixel(40, 0) && lcd.get_pixel(40, 47));
+    assert!(!lcd.get_pixel(1, 1) && !lcd.get_pixel(82, 46));
+}
+
+#[test]
+fn fill_rect_with_pattern() {
+    // Diagonal hatching.
+    let hatch = [0x80, 0x40, 0x20, 0x10, 0x08, 0x04, 0x02, 0x01];
+    let mut lcd = common::display();
+    lcd.fill_region(0, 0, 20, 20, true);
+    lcd.fill_rect_pattern(3, 3, 10, 10, &hatch);
+    assert!(lcd.get_pixel(3, 3));
+    assert!(lcd.get_pixel(12, 12));
+    assert!(lcd.get_pixel(11, 3));
+    assert!(!lcd.get_pixel(4, 3));
+    assert!(lcd.get_pixel(13, 3));
+}
+
+#[test]
+fn draw_bitmap_transparency() {
+    // Two rows of 10 pixels: the second byte of each row holds 2 pixels
+    // and 6 bits of padding.
+    let data = [0b1010_0000, 0b0111_1111, 0b0000_0001, 0b1000_0000];
+
+    let mut lcd = common::display();
+    lcd.fill_region(0, 0, 20, 4, true);
+    lcd.draw_bitmap(2, 1, 10, 2, &data, false);
+    let row = |lcd : &pcd8544::PCD8544<_, _, _>, y| (0..14).map(|x| lcd.get_pixel(x, y) as u8).collect::<Vec<_>>();
+    assert_eq!(row(&lcd, 1), vec![1, 1, 1, 0, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1]);
+    assert_eq!(row(&lcd, 2), vec![1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 1]);
+    assert_eq!(row(&lcd, 0), vec![1 ; 14]);
+    assert_eq!(row(&lcd, 3), vec![1 ; 14]);
+
+    // Clear bits leave the background untouched.
+    lcd.clear();
+    lcd.set_pixel(3, 1, true);
+    lcd.draw_bitmap(2, 1, 10, 2, &data, true);
+    assert_eq!(row(&lcd, 1), vec![0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0]);
+    assert_eq!(row(&lcd, 2), vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0]);
+
+    // Missing bytes are clear bits, and pixels off the display are ignored.
+    lcd.clear();
+    lcd.draw_bitmap(80, 46, 10, 4, &data, false);
+    assert!(lcd.get_pixel(80, 46) && lcd.get_pixel(82, 46));
+    assert!(!lcd.get_pixel(81, 46) && !lcd.get_pixel(83, 47));
+    assert_eq!(lcd.buffer().iter().map(|b| b.count_ones()).sum::<u32>(), 2);
+}
@@ -0,0 +1,208 @@
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+#[test]
+fn set_buffer_checks_size() {
+    let mut lcd = common::display();
+    assert!(lcd.set_buffer(&[0xFF ; 503]).is_err());
+    assert!(lcd.buffer().iter().all(|&b| b == 0x00));
+
+    let mut data = [0x00 ; 504];
+    data[84 + 2] = 0x01;
+    lcd.set_buffer(&data).unwrap();
+    assert!(lcd.get_pixel(2, 8));
+    assert_eq!(lcd.buffer(), &data[..]);
+}
+
+// A bus that fails every other write once `flaky` is set.
+struct FlakySpi {
+    flaky : Rc<Cell<bool>>,
+    fail : bool
+}
+
+impl embedded_hal::blocking::spi::Write<u8> for FlakySpi {
+    type Error = ();
+
+    fn write(&mut self, _ : &[u8]) -> Result<(), ()> {
+        if !self.flaky.get() {
+            return Ok(())
+        }
+        self.fail = !self.fail;
+        if self.fail { Err(()) } else { Ok(()) }
+    }
+}
+
+#[test]
+fn spi_write_retries() {
+    let flaky = Rc::new(Cell::new(false));
+    let spi = FlakySpi { flaky : flaky.clone(), fail : false };
+    let mut lcd = pcd8544::PCD8544::from_hal(spi, common::NoPin, common::NoPin).unwrap_or_else(|_| panic!());
+    flaky.set(true);
+    assert!(lcd.send_data_byte(0x00).is_err());
+    assert!(lcd.send_data_byte(0x00).is_ok());
+
+    lcd.set_spi_write_retries(2);
+    lcd.set_max_transfer_bytes(84);
+    assert!(lcd.update_full().is_ok());
+    assert!(lcd.send_command(0x20).is_ok());
+}
+
+// A bus failing the given number of writes.
+#[cfg(feature = "std")]
+struct FailingSpi(Rc<Cell<u32>>);
+
+#[cfg(feature = "std")]
+impl embedded_hal::blocking::spi::Write<u8> for FailingSpi {
+    type Error = ();
+
+    fn write(&mut self, _ : &[u8]) -> Result<(), ()> {
+        match self.0.get() {
+            0 => Ok(()),
+            n => {
+                self.0.set(n - 1);
+                Err(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn calibrate_contrast_restores_after_errors() {
+    use std::time::Duration;
+
+    let failures = Rc::new(Cell::new(0));
+    let spi = FailingSpi(failures.clone());
+    let mut lcd = pcd8544::PCD8544::from_hal(spi, common::NoPin, common::NoPin).unwrap_or_else(|_| panic!());
+    lcd.set_pixel(1, 1, true);
+    let saved = lcd.buffer().to_vec();
+
+    // A write fails during the sweep: the contrast and the display are
+    // restored before returning the error.
+    let res = lcd.calibrate_contrast(Duration::from_millis(0), |c| { failures.set((c == 24) as u32); false });
+    assert!(res.is_err());
+    assert_eq!(lcd.buffer(), &saved[..]);
+    assert_eq!(lcd.contrast(), 40);
+    assert!(!lcd.is_dirty());
+
+    // When the restore fails too, the buffer is still restored and the
+    // error of the sweep is returned.
+    let res = lcd.calibrate_contrast(Duration::from_millis(0), |c| { failures.set((c == 24) as u32 * 100); false });
+    assert!(res.is_err());
+    failures.set(0);
+    assert_eq!(lcd.buffer(), &saved[..]);
+    assert!(lcd.is_dirty());
+}
+
+// A bus recording the length of each write.
+struct LengthSpi(Rc<RefCell<Vec<usize>>>);
+
+impl embedded_hal::blocking::spi::Write<u8> for LengthSpi {
+    type Error = ();
+
+    fn write(&mut self, words : &[u8]) -> Result<(), ()> {
+        self.0.borrow_mut().push(words.len());
+        Ok(())
+    }
+}
+
+#[test]
+fn max_transfer_bytes_chunks() {
+    let lengths = Rc::new(RefCell::new(Vec::new()));
+    let spi = LengthSpi(lengths.clone());
+    let mut lcd = pcd8544::PCD8544::from_hal(spi, common::NoPin, common::NoPin).unwrap_or_else(|_| panic!());
+
+    // The two address commands, then the data.
+    lengths.borrow_mut().clear();
+    lcd.update_full().unwrap();
+    assert_eq!(*lengths.borrow(), vec![1, 1, 504]);
+
+    lcd.set_max_transfer_bytes(100);
+    lengths.borrow_mut().clear();
+    lcd.update_full().unwrap();
+    assert_eq!(*lengths.borrow(), vec![1, 1, 100, 100, 100, 100, 100, 4]);
+
+    // The chunks only cover the dirty range.
+    lcd.set_pixel(0, 8, true);
+    lcd.set_pixel(0, 24, true);
+    lengths.borrow_mut().clear();
+    lcd.update().unwrap();
+    assert_eq!(*lengths.borrow(), vec![1, 1, 100, 69]);
+
+    // A limit of 0 sends one byte per write.
+    lcd.set_max_transfer_bytes(0);
+    lcd.set_pixel(5, 0, true);
+    lcd.set_pixel(7, 0, true);
+    lengths.borrow_mut().clear();
+    lcd.update().unwrap();
+    assert_eq!(*lengths.borrow(), vec![1 ; 5]);
+}
+
+#[test]
+fn raw_buffer_access() {
+    let mut lcd = common::display();
+    lcd.buffer_mut()[5 + 2 * 84] = 0x08;
+    assert!(lcd.get_pixel(5, 19));
+    assert_eq!(lcd.buffer().iter().map(|b| b.count_ones()).sum::<u32>(), 1);
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let mut lcd = common::display();
+    lcd.set_pixel(3, 3, true);
+    let snap = lcd.snapshot();
+    lcd.update().unwrap();
+
+    lcd.fill_region(10, 10, 20, 20, true);
+    lcd.set_pixel(3, 3, false);
+    lcd.restore(&snap);
+    assert!(lcd.is_dirty());
+    assert_eq!(lcd.buffer(), &snap[..]);
+
+    lcd.update().unwrap();
+    lcd.restore(&snap);
+    assert!(!lcd.is_dirty());
+}
+
+// The buffer byte and bit set by the top left and bottom right pixels.
+fn corners(orient : pcd8544::Orientation) -> [(usize, u8) ; 2] {
+    let mut lcd = common::display();
+    lcd.orient = orient;
+    let (w, h) = (lcd.width(), lcd.height());
+    let mut res = [(0, 0) ; 2];
+    for (k, &(x, y)) in [(0, 0), (w - 1, h - 1)].iter().enumerate() {
+        lcd.clear();
+        lcd.set_pixel(x, y, true);
+        let index = lcd.buffer().iter().position(|&b| b != 0).unwrap();
+        res[k] = (index, lcd.buffer()[index]);
+    }
+    res
+}
+
+#[test]
+fn orientation_landscape() {
+    assert_eq!(corners(pcd8544::Orientation::Landscape(false)), [(0, 0x01), (503, 0x80)]);
+}
+
+#[test]
+fn orientation_landscape_flipped() {
+    // Rotated by 180 degrees.
+    assert_eq!(corners(pcd8544::Orientation::Landscape(true)), [(503, 0x80), (0, 0x01)]);
+}
+
+#[test]
+fn orientation_portrait() {
+    // Rotated by 90 degrees clockwise: the top left pixel is the native top right.
+    assert_eq!(corners(pcd8544::Orientation::Portrait(false)), [(83, 0x01), (420, 0x80)]);
+}
+
+#[test]
+fn orientation_portrait_flipped() {
+    // Rotated by 90 degrees counterclockwise: the top left pixel is the native bottom left.
+    assert_eq!(corners(pcd8544::Orientation::Portrait(true)), [(420, 0x80), (83, 0x01)]);
+}
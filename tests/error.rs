@@ -0,0 +1,40 @@
+#![cfg(feature = "std")]
+
+extern crate pcd8544;
+
+use pcd8544::Error;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+type TestError = Error<io::Error, fmt::Error>;
+
+#[test]
+fn error_display() {
+    let e : TestError = Error::SpiDevError(io::Error::other("bus down"));
+    assert_eq!(e.to_string(), "SPI write failed: bus down");
+    let e : TestError = Error::PinError(fmt::Error);
+    assert_eq!(e.to_string(), format!("GPIO pin error: {}", fmt::Error));
+    assert_eq!(TestError::Format.to_string(), "invalid image format");
+    assert_eq!(TestError::InvalidParameter.to_string(), "parameter out of range");
+    assert_eq!(TestError::InvalidBufferSize.to_string(), "invalid buffer size");
+    let e : TestError = Error::Io(io::Error::new(io::ErrorKind::NotFound, "no file"));
+    assert_eq!(e.to_string(), "I/O error: no file");
+}
+
+#[test]
+fn error_source() {
+    let e : TestError = Error::SpiDevError(io::Error::other("bus down"));
+    assert_eq!(e.source().unwrap().to_string(), "bus down");
+    let e : TestError = Error::PinError(fmt::Error);
+    assert!(e.source().unwrap().is::<fmt::Error>());
+    let e : TestError = Error::Io(io::Error::new(io::ErrorKind::NotFound, "no file"));
+    assert!(e.source().unwrap().is::<io::Error>());
+    assert!(TestError::Format.source().is_none());
+    assert!(TestError::InvalidParameter.source().is_none());
+    assert!(TestError::InvalidBufferSize.source().is_none());
+
+    // The error can be boxed and propagated with `?`.
+    let boxed : Box<dyn StdError> = Box::new(TestError::InvalidParameter);
+    assert_eq!(boxed.to_string(), "parameter out of range");
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "std")]
+
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+use pcd8544::Orientation;
+
+#[test]
+fn to_ascii_in_each_orientation() {
+    let mut lcd = common::display();
+    lcd.set_pixel(0, 0, true);
+    lcd.set_pixel(83, 47, true);
+    let text = lcd.to_ascii();
+    let lines : Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 48);
+    assert!(lines.iter().all(|l| l.len() == 84));
+    assert_eq!(&lines[0][.. 2], "# ");
+    assert_eq!(&lines[47][82 ..], " #");
+    assert_eq!(text.matches('#').count(), 2);
+    assert!(text.ends_with('\n'));
+
+    lcd.orient = Orientation::Portrait(true);
+    let text = lcd.to_ascii();
+    let lines : Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 84);
+    assert!(lines.iter().all(|l| l.len() == 48));
+    // The native corners are the bottom left and top right in this orientation.
+    assert!(lines[83].starts_with('#'));
+    assert!(lines[0].ends_with('#'));
+}
+
+#[cfg(feature = "export-png")]
+#[test]
+fn to_image_in_each_orientation() {
+    let mut lcd = common::display();
+    lcd.set_pixel(1, 2, true);
+    let image = lcd.to_image();
+    assert_eq!(image.dimensions(), (84, 48));
+    assert_eq!(image.get_pixel(1, 2)[0], 0x00);
+    assert_eq!(image.get_pixel(2, 1)[0], 0xFF);
+    assert_eq!(image.pixels().filter(|p| p[0] == 0x00).count(), 1);
+
+    lcd.orient = Orientation::Portrait(false);
+    lcd.clear();
+    lcd.set_pixel(47, 83, true);
+    let image = lcd.to_image();
+    assert_eq!(image.dimensions(), (48, 84));
+    assert_eq!(image.get_pixel(47, 83)[0], 0x00);
+    assert_eq!(image.pixels().filter(|p| p[0] == 0x00).count(), 1);
+
+    // The image shows the buffer, drawn with the inverse flag as it is.
+    lcd.inverse = true;
+    lcd.set_pixel(0, 0, true);
+    let image = lcd.to_image();
+    assert_eq!(image.get_pixel(0, 0)[0], 0xFF);
+    assert_eq!(image.get_pixel(47, 83)[0], 0x00);
+}
+
+#[cfg(feature = "export-png")]
+#[test]
+fn save_png_round_trip() {
+    let mut lcd = common::display();
+    lcd.fill_region(10, 10, 5, 3, true);
+    let path = std::env::temp_dir().join(format!("pcd8544-export-{}.png", std::process::id()));
+    lcd.save_png(&path).unwrap();
+    let image = image::open(&path).unwrap().to_luma8();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(image, lcd.to_image());
+}
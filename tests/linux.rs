@@ -0,0 +1,35 @@
+#![cfg(feature = "linux")]
+
+extern crate pcd8544;
+
+use pcd8544::{PCD8544Builder, Error, Orientation, SPI_MODE_0, SPI_MODE_1, SPI_MODE_2, SPI_MODE_3};
+
+#[test]
+fn builder_rejects_unsupported_spi_settings() {
+    // The settings are checked before opening the device.
+    for &mode in [SPI_MODE_1, SPI_MODE_2, SPI_MODE_3].iter() {
+        let res = PCD8544Builder::new().spi_mode(mode).build(23, 24, "/dev/nonexistent");
+        assert!(matches!(res, Err(Error::InvalidParameter)), "{:?}", mode);
+    }
+    for &bits in [7, 9, 16].iter() {
+        let res = PCD8544Builder::new().bits_per_word(bits).build(23, 24, "/dev/nonexistent");
+        assert!(matches!(res, Err(Error::InvalidParameter)), "{} bits", bits);
+    }
+
+    // The supported settings go on to open the device.
+    let res = PCD8544Builder::new().spi_mode(SPI_MODE_0).bits_per_word(8).build(23, 24, "/dev/nonexistent");
+    assert!(matches!(res, Err(Error::SpiDevError(_))));
+}
+
+#[test]
+fn builder_opens_the_given_device() {
+    let res = PCD8544Builder::default()
+        .spi_speed_hz(1_000_000)
+        .contrast(50)
+        .orientation(Orientation::Portrait(false))
+        .build(23, 24, "/dev/nonexistent");
+    match res {
+        Err(Error::SpiDevError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        _ => panic!("the device does not exist")
+    }
+}
@@ -0,0 +1,542 @@
+#![cfg(feature = "mock")]
+
+extern crate embedded_hal;
+extern crate pcd8544;
+
+use pcd8544::{MockPCD8544, Orientation};
+
+#[test]
+fn init_sequence() {
+    let lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    // Contrast 40 and bias 4, each followed by a return to normal mode.
+    assert_eq!(lcd.sent_commands(), vec![0x21, 0xA8, 0x20, 0x0C, 0x21, 0x14, 0x20, 0x0C]);
+    assert!(lcd.sent_data().is_empty());
+}
+
+#[test]
+fn update_sends_dirty_bytes() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+
+    lcd.clear_sent();
+    lcd.set_pixel(3, 9, true);
+    lcd.update().unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x41, 0x83]);
+    assert_eq!(lcd.sent_data(), vec![0x02]);
+}
+
+#[test]
+fn update_region_rows_and_clipping() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.fill_region(0, 0, 84, 48, true);
+    lcd.clear_sent();
+
+    // Rows 7 to 17 cover the byte rows 0 to 2.
+    lcd.update_region(10, 7, 3, 11).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x40, 0x8A, 0x41, 0x8A, 0x42, 0x8A]);
+    assert_eq!(lcd.sent_data(), vec![0xFF ; 9]);
+
+    // The region is clipped to the display.
+    lcd.clear_sent();
+    lcd.update_region(80, 44, 100, 100).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x45, 0x80 | 80]);
+    assert_eq!(lcd.sent_data().len(), 4);
+
+    // Empty or off-screen regions send nothing.
+    lcd.clear_sent();
+    lcd.update_region(10, 10, 0, 5).unwrap();
+    lcd.update_region(84, 0, 5, 5).unwrap();
+    lcd.update_region(0, 48, 5, 5).unwrap();
+    assert!(lcd.sent_commands().is_empty() && lcd.sent_data().is_empty());
+}
+
+#[cfg(feature = "diff-update")]
+#[test]
+fn update_diff_sends_changed_ranges() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    // The display memory is unknown after a reset.
+    lcd.update_diff().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+
+    lcd.clear_sent();
+    lcd.set_pixel(3, 9, true);
+    lcd.set_pixel(5, 9, true);
+    lcd.set_pixel(80, 47, true);
+    lcd.update_diff().unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x41, 0x83, 0x45, 0x80 | 80]);
+    assert_eq!(lcd.sent_data(), vec![0x02, 0x00, 0x02, 0x80]);
+
+    // Nothing changed.
+    lcd.clear_sent();
+    lcd.update_diff().unwrap();
+    assert!(lcd.sent_data().is_empty());
+
+    // Most bytes changed.
+    lcd.clear_sent();
+    lcd.fill_region(0, 0, 84, 40, true);
+    lcd.update_diff().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+}
+
+#[cfg(feature = "diff-update")]
+#[test]
+fn data_bytes_invalidate_update_diff() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update_diff().unwrap();
+    lcd.clear_sent();
+    lcd.send_data_byte(0xFF).unwrap();
+    lcd.set_pixel(0, 0, true);
+    lcd.update_diff().unwrap();
+    assert_eq!(lcd.sent_data().len(), 1 + 504);
+
+    // The display memory is known again after the full write.
+    lcd.clear_sent();
+    lcd.set_pixel(1, 0, true);
+    lcd.update_diff().unwrap();
+    assert_eq!(lcd.sent_data(), vec![0x01]);
+}
+
+#[test]
+fn power_up_restores_settings() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.set_contrast(50).unwrap();
+    lcd.set_bias(3).unwrap();
+    lcd.set_temperature_coefficient(2).unwrap();
+    lcd.set_display_inverted(true).unwrap();
+    lcd.update().unwrap();
+
+    lcd.clear_sent();
+    lcd.power_down().unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x24]);
+
+    lcd.clear_sent();
+    lcd.power_up().unwrap();
+    assert_eq!(lcd.sent_commands(), vec![
+        0x20, 0x0D,
+        0x21, 0x80 | 50, 0x20, 0x0D,
+        0x21, 0x13, 0x20, 0x0D,
+        0x21, 0x06, 0x20, 0x0D
+    ]);
+    assert_eq!((lcd.contrast(), lcd.bias()), (50, 3));
+
+    // The display memory may have been lost: the next update sends all of it.
+    lcd.update().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+}
+
+#[cfg(feature = "double-buffer")]
+#[test]
+fn power_up_while_double_buffered() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update().unwrap();
+    lcd.draw_to_back();
+    lcd.power_down().unwrap();
+    lcd.power_up().unwrap();
+    lcd.clear_sent();
+    lcd.update().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+}
+
+#[test]
+fn temperature_coefficient() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.clear_sent();
+    lcd.set_temperature_coefficient(2).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x21, 0x06, 0x20, 0x0C]);
+    assert!(lcd.set_temperature_coefficient(4).is_err());
+}
+
+#[test]
+fn display_inversion_in_the_controller() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.set_pixel(0, 0, true);
+    let before = lcd.buffer().to_vec();
+    lcd.clear_sent();
+    lcd.set_display_inverted(true).unwrap();
+    lcd.set_display_inverted(false).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x0D, 0x0C]);
+    assert!(lcd.sent_data().is_empty());
+    assert_eq!(lcd.buffer(), &before[..]);
+    assert!(!lcd.inverse);
+}
+
+#[test]
+fn display_test_modes() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.clear_sent();
+    lcd.all_segments_on().unwrap();
+    lcd.blank().unwrap();
+    lcd.display_normal().unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x09, 0x08, 0x0C]);
+    assert!(lcd.sent_data().is_empty());
+
+    // The mode is kept across extended commands.
+    lcd.blank().unwrap();
+    lcd.clear_sent();
+    lcd.set_contrast(40).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x21, 0xA8, 0x20, 0x08]);
+}
+
+#[test]
+fn contrast_and_bias_getters() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    assert_eq!((lcd.contrast(), lcd.bias()), (40, 4));
+    lcd.set_contrast(0).unwrap();
+    lcd.set_bias(7).unwrap();
+    assert_eq!((lcd.contrast(), lcd.bias()), (0, 7));
+    lcd.set_contrast(127).unwrap();
+    lcd.set_bias(0).unwrap();
+    assert_eq!((lcd.contrast(), lcd.bias()), (127, 0));
+}
+
+#[test]
+fn bias_out_of_range() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.clear_sent();
+    assert!(lcd.set_bias(8).is_err());
+    assert!(lcd.sent_commands().is_empty());
+    assert_eq!(lcd.bias(), 4);
+}
+
+#[test]
+fn contrast_out_of_range() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.clear_sent();
+    assert!(lcd.try_set_contrast(128).is_err());
+    assert!(lcd.sent_commands().is_empty());
+    lcd.set_contrast(200).unwrap();
+    assert_eq!(lcd.contrast(), 127);
+}
+
+#[test]
+fn animation_player_ticks() {
+    use pcd8544::{Animation, AnimationPlayer};
+    use std::time::Duration;
+
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update().unwrap();
+    let anim = Animation { frames : vec![[0x01 ; 504], [0x02 ; 504]], frame_delay : Duration::from_millis(1) };
+
+    let mut player = AnimationPlayer::new(&anim);
+    lcd.clear_sent();
+    assert!(player.tick(&mut lcd).unwrap());
+    assert_eq!(lcd.sent_data(), vec![0x01 ; 504]);
+    assert!(player.tick(&mut lcd).unwrap());
+    assert!(player.is_finished());
+    assert!(!player.tick(&mut lcd).unwrap());
+
+    let mut player = AnimationPlayer::new(&anim).looping(true);
+    for _ in 0..3 {
+        assert!(player.tick(&mut lcd).unwrap());
+    }
+    assert!(lcd.get_pixel(0, 0));
+
+    lcd.play_animation(&anim).unwrap();
+    assert!(lcd.get_pixel(0, 1));
+}
+
+#[test]
+fn play_animation_skips_last_delay() {
+    use pcd8544::Animation;
+    use std::time::{Duration, Instant};
+
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    let anim = Animation { frames : vec![[0x01 ; 504]], frame_delay : Duration::from_secs(5) };
+    let start = Instant::now();
+    lcd.play_animation(&anim).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(lcd.get_pixel(0, 0));
+}
+
+#[test]
+fn public_result_alias() {
+    fn show(lcd : &mut MockPCD8544) -> pcd8544::MockResult<()> {
+        lcd.print(0, 0, "ok");
+        lcd.update()
+    }
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    assert!(show(&mut lcd).is_ok());
+}
+
+#[test]
+fn reset_with_injected_delay() {
+    use embedded_hal::blocking::delay::DelayMs;
+
+    struct CountDelay(u32);
+
+    impl DelayMs<u16> for CountDelay {
+        fn delay_ms(&mut self, ms : u16) {
+            self.0 += ms as u32;
+        }
+    }
+
+    let mut delay = CountDelay(0);
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.reset_with_delay(&mut delay).unwrap();
+    assert_eq!(delay.0, 100);
+}
+
+#[test]
+fn reset_then_update_sends_whole_buffer() {
+    use embedded_hal::blocking::delay::DelayMs;
+
+    struct NoDelay;
+
+    impl DelayMs<u16> for NoDelay {
+        fn delay_ms(&mut self, _ : u16) {}
+    }
+
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update().unwrap();
+    lcd.reset_with_delay(&mut NoDelay).unwrap();
+    lcd.clear_sent();
+    lcd.update().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+}
+
+#[test]
+fn calibrate_contrast_sweep() {
+    use std::time::Duration;
+
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.set_pixel(1, 1, true);
+    let mut seen = Vec::new();
+    let res = lcd.calibrate_contrast(Duration::from_millis(0), |c| { seen.push(c); c >= 50 }).unwrap();
+    assert_eq!(res, Some(52));
+    assert_eq!(seen, vec![20, 24, 28, 32, 36, 40, 44, 48, 52]);
+    assert_eq!(lcd.contrast(), 52);
+    assert!(lcd.get_pixel(1, 1));
+    assert!(!lcd.get_pixel(0, 1));
+
+    let res = lcd.calibrate_contrast(Duration::from_millis(0), |_| false).unwrap();
+    assert_eq!(res, None);
+    assert_eq!(lcd.contrast(), 52);
+}
+
+#[cfg(feature = "double-buffer")]
+#[test]
+fn calibrate_contrast_while_double_buffered() {
+    use pcd8544::TestPattern;
+    use std::time::Duration;
+
+    let mut pattern = MockPCD8544::new_mock(Orientation::Landscape(false));
+    pattern.fill_test_pattern(TestPattern::Gradient);
+
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.draw_to_back();
+    lcd.set_pixel(1, 1, true);
+    lcd.present().unwrap();
+    let front = lcd.buffer().to_vec();
+    lcd.set_pixel(2, 2, true);
+    let back = lcd.buffer().to_vec();
+
+    lcd.clear_sent();
+    assert_eq!(lcd.calibrate_contrast(Duration::from_millis(0), |c| c >= 24).unwrap(), Some(24));
+    // The pattern is shown, then the presented frame again.
+    let data = lcd.sent_data();
+    assert_eq!(data.len(), 2 * 504);
+    assert_eq!(&data[.. 504], pattern.buffer());
+    assert_eq!(&data[504 ..], &front[..]);
+    // The back buffer is untouched, and still has to be presented.
+    assert_eq!(lcd.buffer(), &back[..]);
+    assert!(lcd.is_dirty());
+    lcd.clear_sent();
+    lcd.present().unwrap();
+    assert_eq!(lcd.sent_data(), vec![0x04]);
+}
+
+#[cfg(all(feature = "dither", feature = "double-buffer"))]
+#[test]
+fn tick_gray_while_double_buffered() {
+    extern crate image;
+
+    // Level 2 of 3 is off in the first subframe and on in the next two.
+    let img = image::GrayImage::from_pixel(2, 2, image::Luma([0x55]));
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update().unwrap();
+    lcd.draw_to_back();
+    lcd.draw_gray_temporal(0, 0, &img, 4);
+    lcd.present().unwrap();
+
+    // The subframes are sent from the presented frame.
+    lcd.clear_sent();
+    lcd.tick_gray().unwrap();
+    assert_eq!(lcd.sent_data(), vec![0x03, 0x03]);
+    lcd.tick_gray().unwrap();
+    lcd.tick_gray().unwrap();
+    assert_eq!(lcd.sent_data(), vec![0x03, 0x03, 0x00, 0x00]);
+    // The back buffer keeps the first subframe.
+    assert!(!lcd.get_pixel(0, 0));
+    assert!(!lcd.is_dirty());
+}
+
+#[test]
+fn custom_init_sequence() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.clear_sent();
+    lcd.init_with(&[0x21, 0xBF, 0x13, 0x20, 0x09]).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x21, 0xBF, 0x13, 0x20, 0x09]);
+    assert!(lcd.sent_data().is_empty());
+}
+
+#[cfg(feature = "double-buffer")]
+#[test]
+fn double_buffering() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update().unwrap();
+    lcd.draw_to_back();
+
+    // A half-drawn frame is not sent.
+    lcd.clear_sent();
+    lcd.set_pixel(3, 9, true);
+    lcd.update().unwrap();
+    assert!(lcd.sent_data().is_empty());
+
+    lcd.set_pixel(5, 9, true);
+    lcd.present().unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x41, 0x83]);
+    assert_eq!(lcd.sent_data(), vec![0x02, 0x00, 0x02]);
+
+    // The last presented frame is sent again after a full update.
+    lcd.clear_sent();
+    lcd.set_pixel(7, 9, true);
+    lcd.update_full().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+    assert_eq!(lcd.sent_data()[84 + 7], 0x00);
+
+    lcd.draw_to_front();
+    lcd.clear_sent();
+    lcd.update().unwrap();
+    assert_eq!(lcd.sent_data()[84 + 7], 0x02);
+}
+
+#[test]
+fn show_lines_and_flush() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.update().unwrap();
+    lcd.clear_sent();
+    lcd.show_lines(&["one", "two", "three", "four", "five"]).unwrap();
+    assert!(!lcd.sent_data().is_empty());
+    // Each line is on its own row, and the fifth one is ignored.
+    assert!((0..18).any(|x| (36..48).any(|y| lcd.get_pixel(x, y))));
+    assert!(!(30..84).any(|x| (0..48).any(|y| lcd.get_pixel(x, y))));
+
+    lcd.clear_sent();
+    lcd.show_text(1, 1, "#").unwrap();
+    assert!(!lcd.sent_data().is_empty());
+    assert!(!(0..6).any(|x| (0..48).any(|y| lcd.get_pixel(x, y))));
+    assert!((6..12).any(|x| (12..24).any(|y| lcd.get_pixel(x, y))));
+}
+
+#[test]
+fn dirty_flag() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    assert!(lcd.is_dirty());
+    lcd.update().unwrap();
+    assert!(!lcd.is_dirty());
+    lcd.set_pixel(1, 1, true);
+    assert!(lcd.is_dirty());
+    lcd.update().unwrap();
+    assert!(!lcd.is_dirty());
+
+    // With double buffering, until the back buffer is presented.
+    #[cfg(feature = "double-buffer")]
+    {
+        lcd.draw_to_back();
+        lcd.set_pixel(2, 2, true);
+        lcd.update().unwrap();
+        assert!(lcd.is_dirty());
+        lcd.present().unwrap();
+        assert!(!lcd.is_dirty());
+    }
+}
+
+#[test]
+fn command_state() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    let state = lcd.state();
+    assert_eq!(state.function_set, Some(0x20));
+    assert_eq!(state.display_control, Some(0x0C));
+    assert_eq!(state.contrast, Some(0xA8));
+    assert_eq!(state.bias, Some(0x14));
+    assert_eq!(state.temperature_coefficient, None);
+
+    lcd.set_temperature_coefficient(2).unwrap();
+    lcd.set_display_inverted(true).unwrap();
+    // 0x0C in the extended instruction set is not a display control.
+    lcd.init_with(&[0x21, 0x0C, 0x20]).unwrap();
+    let state = lcd.state();
+    assert_eq!(state.temperature_coefficient, Some(0x06));
+    assert_eq!(state.display_control, Some(0x0D));
+    assert_eq!(state.function_set, Some(0x20));
+}
+
+#[test]
+fn apply_and_read_config() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    let mut config = lcd.current_config();
+    assert_eq!(config, pcd8544::Config {
+        contrast : 40, bias : 4, orientation : Orientation::Landscape(false), char_spacing : 0, inverse : false
+    });
+
+    config.contrast = 60;
+    config.orientation = Orientation::Portrait(true);
+    config.inverse = true;
+    lcd.clear_sent();
+    lcd.apply_config(&config).unwrap();
+    assert_eq!(lcd.current_config(), config);
+    assert!(lcd.sent_commands().contains(&(0x80 | 60)));
+
+    config.bias = 8;
+    assert!(lcd.apply_config(&config).is_err());
+    assert_eq!(lcd.current_config().bias, 4);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn config_is_serializable() {
+    extern crate serde;
+    fn assert_serde<T : serde::Serialize + serde::de::DeserializeOwned>() {}
+    assert_serde::<pcd8544::Config>();
+}
+
+#[test]
+fn set_address_checks_range() {
+    let mut lcd = MockPCD8544::new_mock(Orientation::Landscape(false));
+    lcd.clear_sent();
+    assert!(lcd.set_address(84, 0).is_err());
+    assert!(lcd.set_address(0, 6).is_err());
+    assert!(lcd.sent_commands().is_empty());
+
+    lcd.set_address(83, 5).unwrap();
+    lcd.send_data_byte(0xFF).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x45, 0x80 | 83]);
+    assert_eq!(lcd.sent_data(), vec![0xFF]);
+}
+
+#[cfg(feature = "linux")]
+#[test]
+fn builder_skips_init() {
+    use pcd8544::PCD8544Builder;
+
+    let builder = PCD8544Builder::new().contrast(55).bias(3).orientation(Orientation::Portrait(false)).skip_init(true);
+    let mut lcd = MockPCD8544::from_builder(&builder).unwrap();
+    assert!(lcd.sent_commands().is_empty());
+    assert_eq!((lcd.contrast(), lcd.bias()), (55, 3));
+    assert_eq!(lcd.width(), 48);
+    // The whole buffer is sent by the first update.
+    lcd.update().unwrap();
+    assert_eq!(lcd.sent_data().len(), 504);
+
+    // Without skip_init, the controller is initialized with the settings.
+    let lcd = MockPCD8544::from_builder(&builder.skip_init(false)).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x21, 0x80 | 55, 0x20, 0x0C, 0x21, 0x13, 0x20, 0x0C]);
+
+    let lcd = MockPCD8544::from_builder(&PCD8544Builder::new().init_commands(&[0x21, 0x14, 0x20]).skip_init(true)).unwrap();
+    assert!(lcd.sent_commands().is_empty());
+    let lcd = MockPCD8544::from_builder(&PCD8544Builder::new().init_commands(&[0x21, 0x14, 0x20])).unwrap();
+    assert_eq!(lcd.sent_commands(), vec![0x21, 0x14, 0x20]);
+
+    assert!(MockPCD8544::from_builder(&PCD8544Builder::new().bias(8)).is_err());
+}
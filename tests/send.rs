@@ -0,0 +1,23 @@
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+use std::thread;
+
+fn assert_send<T : Send>() {}
+
+#[test]
+fn display_is_send() {
+    let mut lcd = common::display();
+    let handle = thread::spawn(move || {
+        lcd.print(0, 0, "thread");
+        lcd
+    });
+    let lcd = handle.join().unwrap();
+    assert!((0..6).any(|x| (0..12).any(|y| lcd.get_pixel(x, y))));
+
+    assert_send::<pcd8544::PCD8544<common::NoSpi, common::NoPin, common::NoPin>>();
+    #[cfg(feature = "linux")]
+    assert_send::<pcd8544::LinuxPCD8544>();
+}
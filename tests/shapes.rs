@@ -0,0 +1,544 @@
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+#[test]
+fn fill_triangle_covers_vertices() {
+    let mut lcd = common::display();
+    lcd.fill_triangle(10, 2, 2, 20, 30, 12, true);
+    assert!(lcd.get_pixel(10, 2));
+    assert!(lcd.get_pixel(2, 20));
+    assert!(lcd.get_pixel(30, 12));
+    assert!(lcd.get_pixel(12, 12));
+    assert!(!lcd.get_pixel(30, 2));
+}
+
+#[test]
+fn fill_triangle_far_vertices() {
+    let mut lcd = common::display();
+    lcd.fill_triangle(0, 0, usize::MAX, 0, 0, usize::MAX, true);
+    assert!(lcd.buffer().iter().all(|&b| b == 0xFF));
+
+    lcd.clear();
+    lcd.fill_triangle(0, 100, usize::MAX, 100, 50, usize::MAX, true);
+    lcd.fill_triangle(0, usize::MAX, usize::MAX, usize::MAX, 5, usize::MAX, true);
+    assert!(lcd.buffer().iter().all(|&b| b == 0));
+}
+
+#[test]
+fn fill_triangle_degenerate() {
+    let mut lcd = common::display();
+    lcd.fill_triangle(0, 0, 5, 5, 10, 10, true);
+    assert!(lcd.get_pixel(5, 5));
+    assert!(!lcd.get_pixel(5, 0));
+
+    lcd.fill_triangle(3, 30, 9, 30, 6, 30, true);
+    assert!((3..=9).all(|x| lcd.get_pixel(x, 30)));
+    assert!(!lcd.get_pixel(2, 30));
+}
+
+#[test]
+fn draw_round_rect_corners() {
+    let mut lcd = common::display();
+    lcd.draw_round_rect(10, 10, 20, 12, 4, true);
+    assert!(lcd.get_pixel(14, 10));
+    assert!(lcd.get_pixel(10, 14));
+    assert!(!lcd.get_pixel(10, 10));
+    assert!(!lcd.get_pixel(29, 21));
+
+    // A radius too large for the rectangle is clamped.
+    lcd.clear();
+    lcd.draw_round_rect(0, 0, 6, 6, 10, true);
+    assert!(lcd.get_pixel(2, 0));
+    assert!(!lcd.get_pixel(0, 0));
+}
+
+#[test]
+fn draw_round_rect_off_screen() {
+    // The right edge is at x = 99, past the display: only the left corners
+    // and the left part of the top and bottom edges are visible.
+    let mut lcd = common::display();
+    lcd.draw_round_rect(80, 10, 20, 12, 3, true);
+    assert!(lcd.get_pixel(83, 10) && lcd.get_pixel(83, 21));
+    assert!(!lcd.get_pixel(83, 11) && !lcd.get_pixel(83, 20));
+    assert!(lcd.get_pixel(80, 13) && !lcd.get_pixel(80, 10));
+
+    // Sizes that would overflow do not panic.
+    lcd.clear();
+    lcd.draw_round_rect(5, 5, usize::MAX, usize::MAX, 2, true);
+    assert!(lcd.get_pixel(7, 5) && lcd.get_pixel(83, 5));
+    assert!(lcd.get_pixel(5, 7) && lcd.get_pixel(5, 47));
+    assert!(!lcd.get_pixel(5, 5));
+}
+
+#[test]
+fn draw_arc_quadrant() {
+    let mut lcd = common::display();
+    lcd.draw_arc(40, 24, 10, 0, 90, true);
+    assert!(lcd.get_pixel(50, 24));
+    assert!(lcd.get_pixel(40, 14));
+    assert!(!lcd.get_pixel(30, 24));
+    assert!(!lcd.get_pixel(40, 34));
+}
+
+#[test]
+fn draw_arc_extreme_values() {
+    // Angles at the ends of the i32 range draw the full circle.
+    let mut full = common::display();
+    full.draw_arc(40, 24, 10, 0, 360, true);
+    let mut lcd = common::display();
+    lcd.draw_arc(40, 24, 10, i32::MIN, i32::MAX, true);
+    assert_eq!(lcd.buffer(), full.buffer());
+
+    // Start angles near i32::MAX are reduced before being moved.
+    lcd.clear();
+    lcd.draw_arc(40, 24, 10, i32::MAX - 10, i32::MAX, true);
+    assert!(lcd.buffer().iter().any(|&b| b != 0));
+
+    // Large radii do not overflow.
+    lcd.clear();
+    lcd.draw_arc(0, 0, usize::MAX, 0, 360, true);
+    lcd.draw_arc(1010, 24, 1000, 170, 190, true);
+    assert!(lcd.get_pixel(10, 24));
+}
+
+#[test]
+fn hline_vline_match_draw_line() {
+    use pcd8544::Orientation;
+
+    for flip in [false, true] {
+        for portrait in [false, true] {
+            let orient = || if portrait { Orientation::Portrait(flip) } else { Orientation::Landscape(flip) };
+            let mut fast = common::display();
+            let mut slow = common::display();
+            fast.orient = orient();
+            slow.orient = orient();
+            fast.hline(3, 5, 20, true);
+            slow.draw_line(3, 5, 22, 5, true);
+            fast.vline(7, 2, 30, true);
+            slow.draw_line(7, 2, 7, 31, true);
+            fast.vline(9, 40, 100, true);
+            slow.draw_line(9, 40, 9, 139, true);
+            for y in 0..84 {
+                for x in 0..84 {
+                    assert_eq!(fast.get_pixel(x, y), slow.get_pixel(x, y), "({}, {})", x, y);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn hline_vline_match_set_pixel() {
+    use pcd8544::Orientation;
+
+    // Landscape(false) takes the fast path, writing whole bytes; the other
+    // orientations set each pixel.
+    let orientations = [
+        Orientation::Landscape(false),
+        Orientation::Landscape(true),
+        Orientation::Portrait(false),
+        Orientation::Portrait(true)
+    ];
+    let mut background = [0x00 ; 504];
+    for (i, b) in background.iter_mut().enumerate() {
+        *b = (i * 37 % 256) as u8;
+    }
+    let mut lcd = common::display();
+    let mut expected = common::display();
+    for &orient in orientations.iter() {
+        for &(value, inverse) in [(true, false), (false, false), (true, true), (false, true)].iter() {
+            for &x in [0, 1, 7, 40, 83, 84, 200].iter() {
+                for &y in [0, 3, 7, 8, 15, 47, 48, 83].iter() {
+                    for &len in [0, 1, 5, 8, 9, 47, 84, 100, usize::MAX].iter() {
+                        for d in [&mut lcd, &mut expected] {
+                            d.set_buffer(&background).unwrap();
+                            d.orient = orient;
+                            d.inverse = inverse;
+                        }
+
+                        lcd.hline(x, y, len, value);
+                        for k in 0..len.min(84) {
+                            expected.set_pixel(x + k, y, value);
+                        }
+                        assert_eq!(lcd.buffer(), expected.buffer(), "hline {:?} {} ({}, {}) {}", orient, value, x, y, len);
+
+                        lcd.vline(x, y, len, value);
+                        for k in 0..len.min(84) {
+                            expected.set_pixel(x, y + k, value);
+                        }
+                        assert_eq!(lcd.buffer(), expected.buffer(), "vline {:?} {} ({}, {}) {}", orient, value, x, y, len);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn hline_vline_to_the_edge() {
+    let mut lcd = common::display();
+    lcd.hline(80, 2, usize::MAX, true);
+    lcd.vline(2, 44, usize::MAX, true);
+    assert!(lcd.get_pixel(83, 2));
+    assert!(lcd.get_pixel(2, 47));
+}
+
+#[test]
+fn clear_region_respects_inverse() {
+    let mut lcd = common::display();
+    lcd.fill_region(0, 0, 84, 48, true);
+    lcd.clear_region(80, 40, 10, 10);
+    assert!(lcd.get_pixel(79, 47));
+    assert!(!lcd.get_pixel(80, 40));
+    assert!(!lcd.get_pixel(83, 47));
+
+    let mut lcd = common::display();
+    lcd.inverse = true;
+    lcd.fill_region(0, 0, 4, 4, true);
+    lcd.clear_region(0, 0, 2, 2);
+    assert!(!lcd.get_pixel(0, 0));
+    assert!(lcd.get_pixel(2, 2));
+}
+
+#[test]
+fn invert_region_in_all_orientations() {
+    use pcd8544::Orientation;
+
+    for orient in [Orientation::Landscape(false), Orientation::Landscape(true),
+                   Orientation::Portrait(false), Orientation::Portrait(true)] {
+        let mut lcd = common::display();
+        lcd.orient = orient;
+        lcd.set_pixel(5, 6, true);
+        lcd.invert_region(4, 3, 10, 12);
+        assert!(!lcd.get_pixel(5, 6));
+        assert!(lcd.get_pixel(4, 3));
+        assert!(lcd.get_pixel(13, 14));
+        assert!(!lcd.get_pixel(14, 14));
+        assert!(!lcd.get_pixel(13, 15));
+        assert!(!lcd.get_pixel(3, 3));
+
+        // Clipped at the far edges.
+        let (w, h) = (lcd.width(), lcd.height());
+        lcd.invert_region(w - 2, h - 2, 10, 10);
+        assert!(lcd.get_pixel(w - 1, h - 1));
+        assert!(!lcd.get_pixel(w - 3, h - 1));
+    }
+}
+
+#[test]
+fn rectangles() {
+    let count = |lcd : &pcd8544::PCD8544<common::NoSpi, common::NoPin, common::NoPin>| {
+        lcd.buffer().iter().map(|b| b.count_ones()).sum::<u32>()
+    };
+    let mut lcd = common::display();
+    lcd.draw_rect(10, 5, 6, 4, true);
+    assert!((10..16).all(|x| lcd.get_pixel(x, 5) && lcd.get_pixel(x, 8)));
+    assert!((5..9).all(|y| lcd.get_pixel(10, y) && lcd.get_pixel(15, y)));
+    assert_eq!(count(&lcd), 16);
+    lcd.clear();
+    lcd.fill_rect(10, 5, 6, 4, true);
+    assert_eq!(count(&lcd), 24);
+
+    // Empty rectangles draw nothing.
+    lcd.clear();
+    lcd.draw_rect(10, 5, 0, 4, true);
+    lcd.fill_rect(10, 5, 6, 0, true);
+    assert_eq!(count(&lcd), 0);
+
+    // Rectangles are clipped to the display.
+    lcd.draw_rect(80, 44, 10, 10, true);
+    assert!((80..84).all(|x| lcd.get_pixel(x, 44)));
+    assert!((44..48).all(|y| lcd.get_pixel(80, y)));
+    assert_eq!(count(&lcd), 7);
+    lcd.clear();
+    lcd.fill_rect(80, 44, usize::MAX, usize::MAX, true);
+    assert_eq!(count(&lcd), 16);
+    lcd.clear();
+    lcd.draw_rect(100, 10, 5, 5, true);
+    lcd.fill_rect(10, 100, 5, 5, true);
+    assert_eq!(count(&lcd), 0);
+}
+
+#[test]
+fn get_pixel_reads_back() {
+    let mut lcd = common::display();
+    assert!(!lcd.get_pixel(5, 9));
+    lcd.set_pixel(5, 9, true);
+    assert!(lcd.get_pixel(5, 9));
+    assert!(!lcd.get_pixel(5, 8) && !lcd.get_pixel(4, 9));
+    assert_eq!(lcd.buffer()[84 + 5], 0x02);
+
+    // Pixels outside the display read as off.
+    assert!(!lcd.get_pixel(84, 0) && !lcd.get_pixel(0, 48) && !lcd.get_pixel(usize::MAX, usize::MAX));
+
+    // The inverse flag applies when reading, as when writing.
+    lcd.inverse = true;
+    assert!(!lcd.get_pixel(5, 9) && lcd.get_pixel(5, 8));
+    lcd.set_pixel(5, 8, false);
+    assert!(!lcd.get_pixel(5, 8));
+    assert_eq!(lcd.buffer()[84 + 5], 0x03);
+}
+
+#[test]
+fn toggle_pixel_flips_bits() {
+    let mut lcd = common::display();
+    lcd.set_pixel(7, 20, true);
+    lcd.update().unwrap();
+    lcd.toggle_pixel(7, 20);
+    assert!(!lcd.get_pixel(7, 20));
+    assert!(lcd.is_dirty());
+    lcd.toggle_pixel(7, 21);
+    lcd.toggle_pixel(7, 21);
+    assert!(!lcd.get_pixel(7, 21));
+
+    // Toggling ignores the inverse flag, and pixels outside the display.
+    lcd.inverse = true;
+    lcd.toggle_pixel(7, 20);
+    assert_eq!(lcd.buffer()[2 * 84 + 7], 0x10);
+    lcd.toggle_pixel(84, 0);
+    lcd.toggle_pixel(0, usize::MAX);
+    assert_eq!(lcd.buffer().iter().map(|b| b.count_ones()).sum::<u32>(), 1);
+}
+
+#[test]
+fn pixel_ops() {
+    use pcd8544::PixelOp;
+
+    let mut lcd = common::display();
+    lcd.set_pixel_op(4, 4, PixelOp::Set);
+    assert!(lcd.get_pixel(4, 4));
+    lcd.set_pixel_op(4, 4, PixelOp::Toggle);
+    assert!(!lcd.get_pixel(4, 4));
+    lcd.set_pixel_op(4, 4, PixelOp::Toggle);
+    lcd.set_pixel_op(4, 4, PixelOp::Clear);
+    assert!(!lcd.get_pixel(4, 4));
+}
+
+#[test]
+fn circles() {
+    let mut lcd = common::display();
+    lcd.draw_circle(40, 24, 10, true);
+    assert!(lcd.get_pixel(50, 24) && lcd.get_pixel(30, 24) && lcd.get_pixel(40, 14) && lcd.get_pixel(40, 34));
+    assert!(!lcd.get_pixel(40, 24) && !lcd.get_pixel(51, 24));
+    lcd.clear();
+    lcd.fill_circle(40, 24, 10, true);
+    assert!(lcd.get_pixel(40, 24) && lcd.get_pixel(50, 24) && lcd.get_pixel(45, 28));
+    assert!(!lcd.get_pixel(51, 24) && !lcd.get_pixel(49, 33));
+
+    // A radius of 0 draws the center only.
+    for fill in [false, true] {
+        lcd.clear();
+        if fill {
+            lcd.fill_circle(5, 6, 0, true);
+        }
+        else {
+            lcd.draw_circle(5, 6, 0, true);
+        }
+        assert!(lcd.get_pixel(5, 6));
+        assert_eq!(lcd.buffer().iter().map(|b| b.count_ones()).sum::<u32>(), 1);
+    }
+
+    // Circles far off the display, or too large, draw nothing.
+    lcd.clear();
+    lcd.draw_circle(usize::MAX, 10, 20, true);
+    lcd.fill_circle(10, usize::MAX, 20, true);
+    lcd.draw_circle(0, 0, usize::MAX, true);
+    lcd.fill_circle(0, 0, usize::MAX, true);
+    assert!(lcd.buffer().iter().all(|&b| b == 0));
+
+    // A large circle crossing the display.
+    lcd.fill_circle(30000, 24, 29990, true);
+    assert!(lcd.get_pixel(10, 24) && !lcd.get_pixel(9, 24));
+}
+
+#[test]
+fn flood_fill_inside_circle() {
+    let mut lcd = common::display();
+    lcd.draw_circle(40, 24, 10, true);
+    lcd.flood_fill(40, 24, true);
+    assert!(lcd.get_pixel(35, 20));
+    assert!(lcd.get_pixel(49, 24));
+    assert!(!lcd.get_pixel(51, 24));
+    assert!(!lcd.get_pixel(0, 0));
+
+    // Fill the outside, in portrait.
+    let mut lcd = common::display();
+    lcd.orient = pcd8544::Orientation::Portrait(false);
+    lcd.draw_rect(5, 5, 10, 10, true);
+    lcd.flood_fill(0, 0, true);
+    assert!(lcd.get_pixel(47, 83));
+    assert!(lcd.get_pixel(20, 10));
+    assert!(!lcd.get_pixel(8, 8));
+}
+
+#[test]
+fn polyline_and_polygon() {
+    let mut lcd = common::display();
+    lcd.draw_polyline(&[], true, true);
+    lcd.draw_polyline(&[(3, 3)], true, true);
+    lcd.fill_polygon(&[], true);
+    assert!(lcd.buffer().iter().all(|&b| b == 0x00));
+
+    lcd.draw_polyline(&[(0, 0), (10, 0), (10, 10)], true, true);
+    assert!(lcd.get_pixel(5, 0));
+    assert!(lcd.get_pixel(5, 5));
+    assert!(!lcd.get_pixel(2, 8));
+
+    // A concave "U" shape.
+    let mut lcd = common::display();
+    lcd.fill_polygon(&[(0, 0), (4, 0), (4, 10), (8, 10), (8, 0), (12, 0), (12, 14), (0, 14)], true);
+    assert!(lcd.get_pixel(2, 5));
+    assert!(lcd.get_pixel(10, 5));
+    assert!(lcd.get_pixel(6, 12));
+    assert!(!lcd.get_pixel(6, 5));
+    assert!(!lcd.get_pixel(13, 5));
+}
+
+#[test]
+fn fill_polygon_far_vertices() {
+    let mut lcd = common::display();
+    lcd.fill_polygon(&[(0, 0), (usize::MAX, 0), (usize::MAX, usize::MAX), (0, usize::MAX)], true);
+    assert!(lcd.buffer().iter().all(|&b| b == 0xFF));
+
+    // A far edge does not change the part of the polygon on the display.
+    let mut near = common::display();
+    near.fill_polygon(&[(10, 10), (50, 10), (10, 40)], true);
+    lcd.clear();
+    lcd.fill_polygon(&[(10, 10), (usize::MAX, 10), (10, 40)], true);
+    assert!(lcd.get_pixel(60, 10) && lcd.get_pixel(83, 39) && !lcd.get_pixel(60, 41));
+    assert!((0..48).all(|y| (0..84).all(|x| !near.get_pixel(x, y) || lcd.get_pixel(x, y))));
+}
+
+#[test]
+fn ellipse_extremes() {
+    let mut lcd = common::display();
+    lcd.draw_ellipse(40, 24, 20, 8, true);
+    assert!(lcd.get_pixel(60, 24) && lcd.get_pixel(20, 24));
+    assert!(lcd.get_pixel(40, 16) && lcd.get_pixel(40, 32));
+    assert!(!lcd.get_pixel(40, 24));
+
+    let mut lcd = common::display();
+    lcd.fill_ellipse(40, 24, 20, 8, true);
+    assert!(lcd.get_pixel(40, 24) && lcd.get_pixel(55, 24));
+    assert!(!lcd.get_pixel(58, 30));
+
+    // Degenerate ellipses are lines.
+    let mut lcd = common::display();
+    lcd.draw_ellipse(10, 10, 0, 5, true);
+    assert!((5..=15).all(|y| lcd.get_pixel(10, y)));
+    lcd.fill_ellipse(30, 10, 4, 0, true);
+    assert!((26..=34).all(|x| lcd.get_pixel(x, 10)));
+    assert!(!lcd.get_pixel(30, 11));
+}
+
+#[test]
+fn ellipse_large_radii() {
+    // The largest radii do not overflow the decision terms.
+    let mut lcd = common::display();
+    lcd.draw_ellipse(32768, 24, 32768, 32768, true);
+    assert!(lcd.get_pixel(0, 24) && !lcd.get_pixel(1, 24));
+    lcd.clear();
+    lcd.fill_ellipse(32768, 24, 32768, 32768, true);
+    assert!(lcd.get_pixel(0, 24) && lcd.get_pixel(83, 24));
+
+    // Larger or far ellipses are not drawn.
+    lcd.clear();
+    lcd.draw_ellipse(10, 10, usize::MAX, 5, true);
+    lcd.fill_ellipse(10, 10, 5, usize::MAX, true);
+    lcd.draw_ellipse(usize::MAX, 10, 5, 5, true);
+    assert!(lcd.buffer().iter().all(|&b| b == 0));
+}
+
+#[test]
+fn draw_line_directions() {
+    let mut lcd = common::display();
+    let drawn = |lcd : &pcd8544::PCD8544<common::NoSpi, common::NoPin, common::NoPin>| {
+        (0..48).flat_map(|y| (0..84).map(move |x| (x, y))).filter(|&(x, y)| lcd.get_pixel(x, y)).collect::<Vec<_>>()
+    };
+
+    // Horizontal and vertical lines, in both directions.
+    lcd.draw_line(10, 5, 4, 5, true);
+    assert_eq!(drawn(&lcd), (4..=10).map(|x| (x, 5)).collect::<Vec<_>>());
+    lcd.clear();
+    lcd.draw_line(7, 2, 7, 6, true);
+    assert_eq!(drawn(&lcd), (2..=6).map(|y| (7, y)).collect::<Vec<_>>());
+
+    // Shallow and steep lines have one pixel per column and per row.
+    lcd.clear();
+    lcd.draw_line(0, 0, 9, 3, true);
+    assert_eq!(drawn(&lcd), vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 1), (5, 2), (6, 2), (7, 2), (8, 3), (9, 3)]);
+    lcd.clear();
+    lcd.draw_line(3, 9, 0, 0, true);
+    assert_eq!(drawn(&lcd), vec![(0, 0), (0, 1), (1, 2), (1, 3), (1, 4), (2, 5), (2, 6), (2, 7), (3, 8), (3, 9)]);
+
+    // A single point.
+    lcd.clear();
+    lcd.draw_line(3, 3, 3, 3, true);
+    assert_eq!(drawn(&lcd), vec![(3, 3)]);
+}
+
+#[test]
+fn draw_line_far_ends() {
+    // Lines with far ends are clipped, and keep their direction.
+    let mut lcd = common::display();
+    lcd.draw_line(0, 0, usize::MAX, usize::MAX, true);
+    assert!((0..48).all(|k| lcd.get_pixel(k, k)));
+    assert_eq!(lcd.buffer().iter().map(|b| b.count_ones()).sum::<u32>(), 48);
+
+    lcd.clear();
+    lcd.draw_line(usize::MAX, 5, 0, 5, true);
+    assert!((0..84).all(|x| lcd.get_pixel(x, 5)));
+
+    let mut near = common::display();
+    near.draw_line(10, 10, 1000, 500, true);
+    lcd.clear();
+    lcd.draw_line(10, 10, 100, 55, true);
+    assert_eq!(lcd.buffer(), near.buffer());
+
+    // Dashes keep their phase when the start of the line is clipped.
+    lcd.clear();
+    lcd.draw_dashed_line(10000, 5, 0, 5, 3, 2, true);
+    for x in 0..84 {
+        assert_eq!(lcd.get_pixel(x, 5), (10000 - x) % 5 < 3, "x = {}", x);
+    }
+}
+
+#[test]
+fn dashed_line_pattern() {
+    let mut lcd = common::display();
+    lcd.draw_dashed_line(0, 5, 20, 5, 3, 2, true);
+    for x in 0..=20 {
+        assert_eq!(lcd.get_pixel(x, 5), x % 5 < 3, "x = {}", x);
+    }
+
+    // Without gaps, the same pixels as draw_line.
+    let mut solid = common::display();
+    solid.draw_line(3, 40, 70, 2, true);
+    lcd.clear();
+    lcd.draw_dashed_line(3, 40, 70, 2, 4, 0, true);
+    assert_eq!(lcd.buffer(), solid.buffer());
+}
+
+#[test]
+fn thick_line_width() {
+    let mut lcd = common::display();
+    lcd.draw_thick_line(10, 20, 60, 20, 3, true);
+    assert!((10..=60).all(|x| (19..=21).all(|y| lcd.get_pixel(x, y))));
+    assert!(!lcd.get_pixel(30, 18) && !lcd.get_pixel(30, 22));
+
+    // Diagonal lines are widened to keep the perpendicular width.
+    lcd.clear();
+    lcd.draw_thick_line(20, 0, 20, 40, 4, true);
+    assert_eq!((0..84).filter(|&x| lcd.get_pixel(x, 10)).count(), 4);
+    lcd.clear();
+    lcd.draw_thick_line(0, 0, 40, 40, 4, true);
+    assert_eq!((0..84).filter(|&x| lcd.get_pixel(x, 20)).count(), 6);
+
+    let mut thin = common::display();
+    thin.draw_line(3, 40, 70, 2, true);
+    lcd.clear();
+    lcd.draw_thick_line(3, 40, 70, 2, 1, true);
+    assert_eq!(lcd.buffer(), thin.buffer());
+}
@@ -0,0 +1,416 @@
+
+extern crate embedded_hal;
+extern crate pcd8544;
+
+use pcd8544::{PCD8544, Orientation, TextDirection};
+use std::fmt::Write;
+
+mod common;
+
+#[test]
+fn print_unknown_char() {
+    let mut lcd = common::display();
+    lcd.print(0, 0, "漢字");
+    // Top-left corner of the replacement glyph box.
+    assert!(lcd.get_pixel(0, 2));
+}
+
+fn cell_is_blank<SPI, DC, RST>(lcd : &PCD8544<SPI, DC, RST>, x : usize, y : usize) -> bool {
+    (x * 6 .. x * 6 + 6).all(|px| (y * 12 .. y * 12 + 12).all(|py| !lcd.get_pixel(px, py)))
+}
+
+#[test]
+fn print_wraps_in_portrait() {
+    let mut lcd = common::display();
+    lcd.orient = Orientation::Portrait(false);
+    // The display is 48 pixels wide, i.e. 8 columns.
+    lcd.print(0, 0, "        #");
+    assert!(cell_is_blank(&lcd, 0, 0));
+    assert!(!cell_is_blank(&lcd, 0, 1));
+}
+
+#[test]
+fn print_control_chars() {
+    let mut lcd = common::display();
+    lcd.print(1, 0, "#\n#\r\t#");
+    assert!(!cell_is_blank(&lcd, 1, 0));
+    assert!(!cell_is_blank(&lcd, 1, 1));
+    assert!(!cell_is_blank(&lcd, 4, 1));
+    assert!(cell_is_blank(&lcd, 0, 1));
+    assert!(cell_is_blank(&lcd, 2, 1));
+}
+
+#[test]
+fn print_wrapped_returns_rest() {
+    let mut lcd = common::display();
+    let rest = lcd.print_wrapped(0, 0, 6, "The quick brown fox jumps over the lazy dog");
+    assert_eq!(rest, "jumps over the lazy dog");
+    assert!(!cell_is_blank(&lcd, 0, 3));
+}
+
+#[test]
+fn write_at_cursor() {
+    let mut lcd = common::display();
+    lcd.set_cursor(2, 1);
+    write!(lcd, "{}\n{}", 1, 2).unwrap();
+    assert!(!cell_is_blank(&lcd, 2, 1));
+    assert!(!cell_is_blank(&lcd, 0, 2));
+}
+
+#[test]
+fn println_scrolls() {
+    let mut lcd = common::display();
+    for line in &["#", "", "", "", "#"] {
+        lcd.println(line);
+    }
+    // The first line scrolled out, the last one is on the last row.
+    assert!(cell_is_blank(&lcd, 0, 0));
+    assert!(!cell_is_blank(&lcd, 0, 3));
+}
+
+#[test]
+fn print_with_codepage() {
+    use pcd8544::CodePage;
+
+    // Byte 0xDB is a full block in code page 437.
+    let text : String = [0xDBu8].iter().map(|&b| b as char).collect();
+    let mut lcd = common::display();
+    lcd.set_codepage(CodePage::Cp437);
+    lcd.print(0, 0, &text);
+    assert!((0..6).all(|x| (0..12).all(|y| lcd.get_pixel(x, y))));
+
+    let mut lcd = common::display();
+    lcd.print(0, 0, &text);
+    assert!(!(0..6).all(|x| (0..12).all(|y| lcd.get_pixel(x, y))));
+}
+
+#[test]
+fn proportional_spacing() {
+    let mut lcd = common::display();
+    assert_eq!(lcd.measure_text("il"), 12);
+    lcd.set_proportional(true);
+    let w = lcd.measure_text("il");
+    assert!(w < 12, "{}", w);
+    assert_eq!(lcd.measure_text(" "), 3);
+
+    // The second glyph starts right after the first one.
+    lcd.print(0, 0, "ll");
+    assert!((w / 2 .. w).any(|x| (0..12).any(|y| lcd.get_pixel(x, y))));
+    assert!((w .. 12).all(|x| (0..12).all(|y| !lcd.get_pixel(x, y))));
+}
+
+#[test]
+fn print_opaque_clears_cells() {
+    let mut lcd = common::display();
+    lcd.char_spacing = 2;
+    lcd.fill_region(0, 0, 84, 12, true);
+    lcd.print_opaque(0, 0, " ");
+    assert!(cell_is_blank(&lcd, 0, 0));
+    // The spacing after the character is cleared too.
+    assert!(!lcd.get_pixel(7, 5));
+    assert!(lcd.get_pixel(8, 5));
+}
+
+#[test]
+fn print_char_exact_pixels() {
+    use pcd8544::{Font, Terminus6x12};
+
+    let mut lcd = common::display();
+    // Pixels on the right of the cell must be left untouched.
+    lcd.fill_region(6, 0, 2, 12, true);
+    lcd.print_char(0, 0, 'A');
+    let glyph = Terminus6x12.glyph('A').unwrap();
+    for (y, &row) in glyph.iter().enumerate() {
+        for x in 0..6 {
+            assert_eq!(lcd.get_pixel(x, y), row & (0x80 >> x) != 0, "({}, {})", x, y);
+        }
+        assert!(lcd.get_pixel(6, y) && lcd.get_pixel(7, y));
+    }
+}
+
+struct WideFont;
+
+impl pcd8544::Font for WideFont {
+    fn width(&self) -> usize {
+        10
+    }
+
+    fn height(&self) -> usize {
+        2
+    }
+
+    fn glyph(&self, _ : char) -> Option<&[u8]> {
+        // Two rows of two bytes: the leftmost and rightmost columns.
+        Some(&[0x80, 0x40, 0x00, 0x00])
+    }
+}
+
+#[test]
+fn print_char_wide_font() {
+    static FONT : WideFont = WideFont;
+    let mut lcd = common::display();
+    lcd.set_font(&FONT);
+    lcd.print_char(1, 0, 'x');
+    assert!(lcd.get_pixel(10, 0));
+    assert!(lcd.get_pixel(19, 0));
+    assert!(!lcd.get_pixel(18, 0));
+    assert!(!lcd.get_pixel(10, 1));
+    assert_eq!((0..84).filter(|&x| lcd.get_pixel(x, 0)).count(), 2);
+}
+
+// A 3x4 font with a glyph for 'A' only, and no replacement character.
+struct BlockFont;
+
+impl pcd8544::Font for BlockFont {
+    fn width(&self) -> usize {
+        3
+    }
+
+    fn height(&self) -> usize {
+        4
+    }
+
+    fn glyph(&self, c : char) -> Option<&[u8]> {
+        use pcd8544::font::*;
+        match c {
+            'A' => Some(&[XXX_____, X_X_____, XXX_____, ________]),
+            _   => None
+        }
+    }
+}
+
+#[test]
+fn set_font_at_runtime() {
+    static FONT : BlockFont = BlockFont;
+    let mut lcd = common::display();
+    lcd.set_font(&FONT);
+    assert_eq!((lcd.text_cols(), lcd.text_rows()), (28, 12));
+
+    // Cells are 3x4, and characters without a glyph are blank.
+    lcd.fill_region(6, 4, 3, 4, true);
+    lcd.print(1, 1, "AB");
+    assert!(lcd.get_pixel(3, 4) && lcd.get_pixel(5, 6) && !lcd.get_pixel(4, 5) && !lcd.get_pixel(3, 7));
+    assert!((6..9).all(|x| (4..8).all(|y| !lcd.get_pixel(x, y))));
+
+    // Back to the default font.
+    lcd.set_font(&pcd8544::Terminus6x12);
+    assert_eq!((lcd.text_cols(), lcd.text_rows()), (14, 4));
+    let mut other = common::display();
+    lcd.clear();
+    lcd.print(0, 0, "A");
+    other.print(0, 0, "A");
+    assert_eq!(lcd.buffer(), other.buffer());
+}
+
+#[test]
+fn print_scaled_blocks() {
+    let mut lcd = common::display();
+    lcd.print(1, 0, "Ag");
+    let mut scaled = common::display();
+    scaled.print_scaled(1, 0, "Ag", 1);
+    assert_eq!(scaled.buffer(), lcd.buffer());
+
+    // At scale 2, each pixel of the text is a 2x2 block in 12x24 cells.
+    scaled.clear();
+    scaled.print_scaled(1, 0, "Ag", 2);
+    for y in 0..24 {
+        for x in 0..36 {
+            assert_eq!(scaled.get_pixel(x, y), x >= 12 && lcd.get_pixel(6 + (x - 12) / 2, y / 2), "({}, {})", x, y);
+        }
+    }
+
+    // Scale 0 draws nothing.
+    lcd.clear();
+    lcd.print_scaled(0, 0, "A", 0);
+    assert!(lcd.buffer().iter().all(|&b| b == 0));
+}
+
+#[test]
+fn print_centered_and_right() {
+    let mut lcd = common::display();
+    let mut grid = common::display();
+
+    // "ab" is 12 pixels wide: centered from x = 36, right-aligned from 72.
+    lcd.print_centered(1, "ab");
+    grid.draw_char_at(36, 12, 'a', 1);
+    grid.draw_char_at(42, 12, 'b', 1);
+    lcd.print_right(2, "ab");
+    grid.draw_char_at(72, 24, 'a', 1);
+    grid.draw_char_at(78, 24, 'b', 1);
+    assert_eq!(lcd.buffer(), grid.buffer());
+
+    // Longer strings start at the left edge, and are cut after the last
+    // character that fits entirely.
+    let long = "0123456789abcdefghij";
+    lcd.clear();
+    grid.clear();
+    lcd.print_centered(0, long);
+    lcd.print_right(1, long);
+    grid.print(0, 0, &long[..14]);
+    grid.print(0, 1, &long[..14]);
+    assert_eq!(lcd.buffer(), grid.buffer());
+
+    // With 8-pixel cells, the 11th character would be cut: it is not drawn.
+    lcd.clear();
+    lcd.char_spacing = 2;
+    lcd.print_right(0, long);
+    grid.clear();
+    grid.char_spacing = 2;
+    grid.print(0, 0, &long[..10]);
+    assert_eq!(lcd.buffer(), grid.buffer());
+    assert!((80..84).all(|x| (0..12).all(|y| !lcd.get_pixel(x, y))));
+}
+
+#[test]
+fn measure_text_control_chars() {
+    let mut lcd = common::display();
+    assert_eq!(lcd.measure_text(""), 0);
+    assert_eq!(lcd.measure_text("abc"), 18);
+    // The longest line, and a line started over.
+    assert_eq!(lcd.measure_text("ab\ncde\nf"), 18);
+    assert_eq!(lcd.measure_text("abcd\rx"), 24);
+    // Tab stops every 4 cells.
+    assert_eq!(lcd.measure_text("\t"), 24);
+    assert_eq!(lcd.measure_text("a\tb"), 30);
+    assert_eq!(lcd.measure_text("abcd\tb"), 54);
+    // Other control characters are printed as a replacement glyph.
+    assert_eq!(lcd.measure_text("\u{1}"), 6);
+
+    lcd.char_spacing = 1;
+    assert_eq!(lcd.measure_text("ab\tc"), 35);
+}
+
+#[test]
+fn draw_char_at_pixel_position() {
+    let mut grid = common::display();
+    grid.print_char(0, 0, 'A');
+    let mut lcd = common::display();
+    lcd.draw_char_at(5, 3, 'A', 1);
+    for y in 0..12 {
+        for x in 0..6 {
+            assert_eq!(lcd.get_pixel(x + 5, y + 3), grid.get_pixel(x, y));
+        }
+    }
+
+    lcd.clear();
+    lcd.draw_char_at(10, 1, 'A', 2);
+    for y in 0..24 {
+        for x in 0..12 {
+            assert_eq!(lcd.get_pixel(x + 10, y + 1), grid.get_pixel(x / 2, y / 2));
+        }
+    }
+}
+
+#[test]
+fn print_right_to_left() {
+    let mut lcd = common::display();
+    lcd.set_text_direction(TextDirection::RightLeft);
+    // 14 columns: "a#" fills columns 13 and 12, "#" wraps to column 13 of row 1.
+    lcd.print(13, 0, "a#            #");
+    assert!(!cell_is_blank(&lcd, 13, 0));
+    assert!(!cell_is_blank(&lcd, 12, 0));
+    assert!(cell_is_blank(&lcd, 11, 0));
+    assert!(!cell_is_blank(&lcd, 13, 1));
+    assert!(cell_is_blank(&lcd, 12, 1));
+}
+
+#[test]
+fn print_top_to_bottom() {
+    let mut lcd = common::display();
+    lcd.set_text_direction(TextDirection::TopBottom);
+    lcd.print(2, 1, "##\n#");
+    assert!(cell_is_blank(&lcd, 2, 0));
+    assert!(!cell_is_blank(&lcd, 2, 1));
+    assert!(!cell_is_blank(&lcd, 2, 2));
+    assert!(!cell_is_blank(&lcd, 3, 1));
+    assert!(cell_is_blank(&lcd, 3, 0));
+
+    // 4 rows: the third character wraps to the top of the next column.
+    lcd.clear();
+    lcd.print(0, 2, "####");
+    assert!(!cell_is_blank(&lcd, 0, 3));
+    assert!(!cell_is_blank(&lcd, 1, 0));
+    assert!(!cell_is_blank(&lcd, 1, 1));
+    assert!(cell_is_blank(&lcd, 1, 2));
+}
+
+#[test]
+fn print_inverse_negative() {
+    let mut normal = common::display();
+    normal.print(1, 1, "Ab");
+    let mut lcd = common::display();
+    lcd.print_inverse(1, 1, "Ab");
+    for y in 12..24 {
+        for x in 6..18 {
+            assert_eq!(lcd.get_pixel(x, y), !normal.get_pixel(x, y));
+        }
+    }
+    assert!(cell_is_blank(&lcd, 0, 1));
+    assert!(cell_is_blank(&lcd, 3, 1));
+
+    // With inverse set, the negative of the negative.
+    lcd.clear();
+    lcd.inverse = true;
+    lcd.print_inverse(1, 1, "Ab");
+    lcd.inverse = false;
+    for y in 12..24 {
+        for x in 6..18 {
+            assert_eq!(lcd.get_pixel(x, y), normal.get_pixel(x, y));
+        }
+    }
+}
+
+#[test]
+fn draw_text_in_box_clips_and_truncates() {
+    let text = "## ## ## ## ##";
+    let mut lcd = common::display();
+    lcd.draw_text_in_box(3, 2, 36, 30, text);
+    // Two rows of 6 cells: "## ##", then "##" and an ellipsis.
+    assert!(lcd.get_pixel(3 + 24 + 1, 2 + 4));
+    assert!(lcd.get_pixel(3 + 1, 14 + 4));
+    assert!((3 + 12 .. 3 + 30).any(|x| (14 .. 26).any(|y| lcd.get_pixel(x, y))));
+    assert!((0..84).all(|x| (26..48).all(|y| !lcd.get_pixel(x, y))));
+
+    // The pixels around the box are left unchanged.
+    let mut lcd = common::display();
+    lcd.fill_region(0, 0, 84, 48, true);
+    lcd.draw_text_in_box(3, 2, 34, 30, text);
+    for x in 0..84 {
+        for y in 0..48 {
+            if !(3..37).contains(&x) || !(2..32).contains(&y) {
+                assert!(lcd.get_pixel(x, y), "({}, {})", x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn cursor_blinks_after_text() {
+    let mut lcd = common::display();
+    write!(lcd, "ab").unwrap();
+    lcd.tick_cursor();
+    assert!(lcd.cursor().visible);
+    assert!((12..18).all(|x| (0..12).all(|y| lcd.get_pixel(x, y))));
+    lcd.tick_cursor();
+    assert!(cell_is_blank(&lcd, 2, 0));
+
+    // Printing removes the block, which then follows the text.
+    lcd.tick_cursor();
+    write!(lcd, "c").unwrap();
+    assert!(!lcd.cursor().visible);
+    assert!(!(12..18).all(|x| (0..12).all(|y| lcd.get_pixel(x, y))));
+    lcd.move_cursor(0, 2);
+    lcd.tick_cursor();
+    lcd.move_cursor(1, 2);
+    assert!(cell_is_blank(&lcd, 0, 2));
+    assert!(lcd.get_pixel(6, 24) && lcd.cursor().visible);
+}
+
+#[test]
+fn text_grid_size() {
+    let mut lcd = common::display();
+    assert_eq!((lcd.text_cols(), lcd.text_rows()), (14, 4));
+    lcd.orient = Orientation::Portrait(false);
+    lcd.char_spacing = 1;
+    assert_eq!((lcd.text_cols(), lcd.text_rows()), (6, 7));
+}
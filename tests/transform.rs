@@ -0,0 +1,112 @@
+
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+use pcd8544::Orientation;
+
+#[test]
+fn scroll_across_byte_rows() {
+    let mut lcd = common::display();
+    lcd.set_pixel(10, 6, true);
+    lcd.scroll_vertical(5);
+    assert!(!lcd.get_pixel(10, 6));
+    assert!(lcd.get_pixel(10, 11));
+    lcd.scroll_vertical(-11);
+    assert!(lcd.get_pixel(10, 0));
+    lcd.scroll_horizontal(-10);
+    assert!(lcd.get_pixel(0, 0));
+    lcd.scroll_horizontal(-1);
+    assert!(!(0..84).any(|x| (0..48).any(|y| lcd.get_pixel(x, y))));
+}
+
+#[test]
+fn scroll_in_portrait() {
+    let mut lcd = common::display();
+    lcd.orient = Orientation::Portrait(false);
+    lcd.set_pixel(3, 70, true);
+    lcd.scroll_vertical(10);
+    assert!(lcd.get_pixel(3, 80));
+    lcd.scroll_horizontal(20);
+    assert!(lcd.get_pixel(23, 80));
+}
+
+#[test]
+fn set_orientation_keeps_image() {
+    let mut lcd = common::display();
+    lcd.set_pixel(2, 3, true);
+    lcd.set_pixel(70, 10, true);
+    lcd.set_orientation(Orientation::Landscape(true));
+    assert!(lcd.get_pixel(2, 3));
+    assert!(lcd.get_pixel(70, 10));
+    assert!(!lcd.get_pixel(81, 44));
+
+    // The right part of the image does not fit in portrait.
+    lcd.set_orientation(Orientation::Portrait(false));
+    assert!(lcd.get_pixel(2, 3));
+    lcd.set_orientation(Orientation::Landscape(false));
+    assert!(lcd.get_pixel(2, 3));
+    assert!(!lcd.get_pixel(70, 10));
+}
+
+#[test]
+fn mirror_in_both_directions() {
+    for orient in [Orientation::Landscape(false), Orientation::Portrait(true)] {
+        let mut lcd = common::display();
+        lcd.orient = orient;
+        let (w, h) = (lcd.width(), lcd.height());
+        lcd.set_pixel(1, 9, true);
+        lcd.mirror_horizontal();
+        assert!(lcd.get_pixel(w - 2, 9));
+        assert!(!lcd.get_pixel(1, 9));
+        lcd.mirror_vertical();
+        assert!(lcd.get_pixel(w - 2, h - 10));
+        assert!(!lcd.get_pixel(w - 2, 9));
+    }
+}
+
+#[test]
+fn pixel_bounds_in_all_orientations() {
+    let orientations = [
+        Orientation::Landscape(false),
+        Orientation::Landscape(true),
+        Orientation::Portrait(false),
+        Orientation::Portrait(true)
+    ];
+    for &orient in orientations.iter() {
+        let mut lcd = common::display();
+        lcd.orient = orient;
+        let (w, h) = (lcd.width(), lcd.height());
+
+        // The four corners are on the display.
+        for &(x, y) in [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)].iter() {
+            lcd.set_pixel(x, y, true);
+            assert!(lcd.get_pixel(x, y), "{:?} ({}, {})", orient, x, y);
+        }
+        assert_eq!(lcd.buffer().iter().map(|b| b.count_ones()).sum::<u32>(), 4);
+
+        // Pixels just outside, and far outside, are ignored.
+        lcd.clear();
+        for &(x, y) in [(w, 0), (0, h), (w, h), (84, 47), (47, 84), (usize::MAX, 0), (0, usize::MAX)].iter() {
+            lcd.set_pixel(x, y, true);
+            lcd.toggle_pixel(x, y);
+            assert!(!lcd.get_pixel(x, y));
+        }
+        assert!(lcd.buffer().iter().all(|&b| b == 0x00), "{:?}", orient);
+    }
+}
+
+#[test]
+fn dimensions_follow_orientation() {
+    let mut lcd = common::display();
+    assert_eq!((lcd.width(), lcd.height()), (84, 48));
+    lcd.orient = Orientation::Landscape(true);
+    assert_eq!((lcd.width(), lcd.height()), (84, 48));
+    lcd.orient = Orientation::Portrait(false);
+    assert_eq!((lcd.width(), lcd.height()), (48, 84));
+    lcd.set_orientation(Orientation::Portrait(true));
+    assert_eq!((lcd.width(), lcd.height()), (48, 84));
+    lcd.set_orientation(Orientation::Landscape(false));
+    assert_eq!((lcd.width(), lcd.height()), (84, 48));
+}
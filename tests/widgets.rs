@@ -0,0 +1,193 @@
+extern crate embedded_hal;
+extern crate pcd8544;
+
+mod common;
+
+#[test]
+fn progress_bar_rounding() {
+    let mut lcd = common::display();
+    lcd.draw_progress_bar(0, 0, 12, 5, 0.5, true);
+    // 10 pixels inside the border, half of them filled.
+    assert!(lcd.get_pixel(5, 2));
+    assert!(!lcd.get_pixel(6, 2));
+    assert!(lcd.get_pixel(11, 2));
+
+    lcd.draw_progress_bar(0, 0, 12, 5, 0.04, true);
+    assert!(!lcd.get_pixel(1, 2));
+    lcd.draw_progress_bar(0, 0, 12, 5, 0.06, true);
+    assert!(lcd.get_pixel(1, 2));
+    assert!(!lcd.get_pixel(2, 2));
+
+    lcd.draw_progress_bar(0, 10, 10, 3, 2.0, false);
+    assert!(lcd.get_pixel(9, 12));
+    lcd.draw_progress_bar(0, 10, 10, 3, -1.0, false);
+    assert!(!lcd.get_pixel(0, 10));
+}
+
+#[test]
+fn plot_series_scaling() {
+    use pcd8544::PlotFrame;
+
+    let mut lcd = common::display();
+    lcd.plot_series(0, 0, 10, 11, &[0, 10], PlotFrame::None);
+    assert!(lcd.get_pixel(0, 10));
+    assert!(lcd.get_pixel(9, 0));
+
+    // Equal samples give a line in the middle.
+    let mut lcd = common::display();
+    lcd.plot_series(0, 0, 20, 10, &[3, 3, 3], PlotFrame::Border);
+    assert!((1..19).all(|x| lcd.get_pixel(x, 5)));
+    assert!(!lcd.get_pixel(1, 4));
+
+    // More samples than columns.
+    let samples : Vec<i32> = (0..100).collect();
+    let mut lcd = common::display();
+    lcd.plot_series(0, 0, 10, 10, &samples, PlotFrame::Axes);
+    assert!(lcd.get_pixel(1, 8));
+    assert!(lcd.get_pixel(9, 0));
+}
+
+#[test]
+fn seven_segment_digits() {
+    let mut lcd = common::display();
+    lcd.draw_seven_segment(0, 0, 9, 15, 1, 2);
+    // Only the right segments are lit.
+    assert!(lcd.get_pixel(8, 1));
+    assert!(lcd.get_pixel(8, 13));
+    assert!(!lcd.get_pixel(0, 1));
+    assert!(!lcd.get_pixel(4, 0));
+
+    let end = lcd.draw_number(0, 20, -42, 9, 15, 2);
+    assert_eq!(end, 31);
+    // Minus sign: only the middle segment.
+    assert!(lcd.get_pixel(4, 26));
+    assert!(!lcd.get_pixel(4, 20));
+    // '2': top segment, no bottom right segment.
+    assert!(lcd.get_pixel(26, 20));
+    assert!(!lcd.get_pixel(30, 32));
+}
+
+#[test]
+fn clock_digits_and_colon() {
+    let mut lcd = common::display();
+    let end = lcd.draw_number_padded(0, 0, 7, 2, 9, 15, 2);
+    assert_eq!(end, 20);
+    // Leading '0': bottom left segment lit, no middle segment.
+    assert!(lcd.get_pixel(0, 13));
+    assert!(!lcd.get_pixel(4, 7));
+
+    let end = lcd.draw_colon(end + 2, 0, 15, 2, true);
+    assert_eq!(end, 24);
+    assert!(lcd.get_pixel(22, 4) && lcd.get_pixel(22, 9));
+    assert!(!lcd.get_pixel(22, 7));
+    lcd.draw_colon(22, 0, 15, 2, false);
+    assert!(!lcd.get_pixel(22, 4));
+}
+
+#[test]
+fn battery_outline_and_bolt() {
+    let mut lcd = common::display();
+    lcd.draw_battery(0, 0, 20, 10, 1.0, false);
+    // Full body, terminal centered on the right.
+    assert!(lcd.get_pixel(1, 1));
+    assert!(lcd.get_pixel(17, 8));
+    assert!(lcd.get_pixel(19, 4));
+    assert!(!lcd.get_pixel(19, 0));
+
+    // The bolt shows as pixels off in a full battery.
+    lcd.draw_battery(0, 0, 20, 10, 1.0, true);
+    assert!(!(1..17).all(|x| lcd.get_pixel(x, 5)));
+    assert!(lcd.get_pixel(1, 1));
+}
+
+#[test]
+fn gauge_needle() {
+    let mut lcd = common::display();
+    lcd.draw_gauge(40, 24, 20, 0.5);
+    // Top of the arc, and no arc at the bottom.
+    assert!(lcd.get_pixel(40, 4));
+    assert!(!lcd.get_pixel(40, 44));
+    // Tick marks at both ends.
+    assert!(lcd.get_pixel(23, 34));
+    assert!(lcd.get_pixel(57, 34));
+    // The needle points up.
+    assert!(lcd.get_pixel(40, 14));
+    assert!(!lcd.get_pixel(30, 24));
+
+    // Redrawn in place, pointing to the right.
+    lcd.draw_gauge(40, 24, 20, 2.0);
+    assert!(!lcd.get_pixel(40, 14));
+    assert!(lcd.get_pixel(52, 31));
+}
+
+#[test]
+fn bar_chart() {
+    let mut lcd = common::display();
+    // Three bars of 3 pixels with 1-pixel gaps.
+    lcd.draw_bars(10, 0, 11, 20, &[10, 5, 0]);
+    assert!(lcd.get_pixel(10, 0) && lcd.get_pixel(12, 19));
+    assert!(!lcd.get_pixel(13, 19));
+    assert!(!lcd.get_pixel(14, 9) && lcd.get_pixel(14, 10) && lcd.get_pixel(16, 10));
+    assert!(!(18..21).any(|x| (0..20).any(|y| lcd.get_pixel(x, y))));
+
+    // A single bar fills the width, and zero values draw nothing.
+    lcd.draw_bars(10, 0, 11, 20, &[3]);
+    assert!((10..21).all(|x| lcd.get_pixel(x, 0)));
+    lcd.draw_bars(10, 0, 11, 20, &[0, 0]);
+    assert!(!(10..21).any(|x| (0..20).any(|y| lcd.get_pixel(x, y))));
+}
+
+#[test]
+fn draw_bars_more_values_than_pixels() {
+    let mut lcd = common::display();
+    lcd.draw_bars(10, 5, 2, 20, &[1 ; 10]);
+    for x in 0..84 {
+        for y in 0..48 {
+            let inside = (10..12).contains(&x) && (5..25).contains(&y);
+            assert!(inside || !lcd.get_pixel(x, y), "pixel ({}, {}) outside the area", x, y);
+        }
+    }
+    assert!(lcd.get_pixel(10, 5) && lcd.get_pixel(11, 5));
+}
+
+#[test]
+fn marquee_scrolls_and_wraps() {
+    let column = |lcd : &pcd8544::PCD8544<common::NoSpi, common::NoPin, common::NoPin>, x : usize| {
+        (12..24).map(|y| lcd.get_pixel(x, y) as u32).sum::<u32>()
+    };
+    let mut lcd = common::display();
+    let mut marquee = pcd8544::Marquee::new("##", 12).gap(4);
+    marquee.tick(&mut lcd);
+    let first : Vec<u32> = (0..84).map(|x| column(&lcd, x)).collect();
+    // The text repeats every 16 pixels.
+    assert!(first.iter().any(|&n| n > 0));
+    assert_eq!(first[..68], first[16..]);
+
+    marquee.tick(&mut lcd);
+    let second : Vec<u32> = (0..84).map(|x| column(&lcd, x)).collect();
+    assert_eq!(second[..83], first[1..]);
+
+    for _ in 0..15 {
+        marquee.tick(&mut lcd);
+    }
+    let wrapped : Vec<u32> = (0..84).map(|x| column(&lcd, x)).collect();
+    assert_eq!(wrapped, first);
+}
+
+#[cfg(feature = "icons")]
+#[test]
+fn draw_status_icons() {
+    use pcd8544::{Icon, ICON_SIZE};
+
+    let mut lcd = common::display();
+    lcd.fill_region(0, 0, 84, 8, true);
+    for (k, &icon) in [Icon::Wifi, Icon::Bluetooth, Icon::Battery, Icon::Signal, Icon::Clock].iter().enumerate() {
+        lcd.draw_icon(k * ICON_SIZE, 0, icon);
+    }
+    // The signal bars end on the right edge, the clock has a hand.
+    assert!(lcd.get_pixel(3 * 8 + 7, 0) && !lcd.get_pixel(3 * 8 + 6, 7));
+    assert!(lcd.get_pixel(4 * 8 + 3, 3) && !lcd.get_pixel(4 * 8 + 3, 1));
+    // The background is cleared, and the right of the display is unchanged.
+    assert!(!lcd.get_pixel(0, 6));
+    assert!(lcd.get_pixel(40, 0));
+}